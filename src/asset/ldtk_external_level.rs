@@ -0,0 +1,19 @@
+use bevy::prelude::*;
+
+use crate::ldtk::prelude::Level;
+
+/// A level saved in its own `.ldtkl` file
+#[derive(Asset, TypePath)]
+pub struct LDtkExternalLevel {
+    level: Level,
+}
+
+impl LDtkExternalLevel {
+    pub fn new(level: Level) -> Self {
+        Self { level }
+    }
+
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+}
@@ -0,0 +1,37 @@
+use bevy::asset::{
+    AssetLoader,
+    {LoadContext, io::Reader},
+};
+
+use crate::asset::{
+    ldtk_external_level::LDtkExternalLevel,
+    ldtk_external_level_loader_error::LDtkExternalLevelLoaderError,
+};
+use crate::ldtk::prelude::Level;
+
+#[derive(Default)]
+pub struct LDtkExternalLevelLoader;
+
+impl AssetLoader for LDtkExternalLevelLoader {
+    type Asset = LDtkExternalLevel;
+
+    type Settings = ();
+
+    type Error = LDtkExternalLevelLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _: &Self::Settings,
+        _: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let level = serde_json::from_slice::<Level>(&bytes)?;
+        Ok(LDtkExternalLevel::new(level))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ldtkl"]
+    }
+}
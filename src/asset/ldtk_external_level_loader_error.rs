@@ -0,0 +1,10 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LDtkExternalLevelLoaderError {
+    #[error("encountered io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("deserialize ldtk level json failed: {0}")]
+    Deserialize(#[from] serde_json::Error),
+}
@@ -1,36 +1,176 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, fs, io, path::Path, sync::OnceLock};
 
-use bevy::{asset::AssetPath, prelude::*};
+use bevy::{asset::AssetPath, math::IRect, prelude::*};
+use serde::Serialize;
+use serde_json::ser::{CompactFormatter, Formatter, PrettyFormatter};
 
-use crate::ldtk::prelude::{LdtkJson, LevelAccessor, TilesetDefinition, World};
+use crate::asset::ldtk_project_index::{LDtkProjectIndex, LevelIndex};
+use crate::asset::ldtk_project_saver_error::LDtkProjectSaverError;
+use crate::ldtk::prelude::{
+    EntityDefinition, EntityInstance, EnumDefinition, FieldDefinition, LayerDefinition,
+    LayerInstance, LdtkJson, Level, LevelAccessor, LevelBuilder, LevelBuilderError,
+    TilesetDefinition, World, refresh_dependent_auto_layers,
+};
 
 #[derive(Asset, TypePath)]
 pub struct LDtkProject {
     asset_path: AssetPath<'static>,
     json_data: LdtkJson,
+    /// Cleared by mutable accessors, rebuilt on the next lookup
+    index: OnceLock<LDtkProjectIndex>,
 }
 
 impl LDtkProject {
     pub fn new(asset_path: &AssetPath, json_data: LdtkJson) -> Self {
         let asset_path = asset_path.clone_owned();
+        let index = OnceLock::from(LDtkProjectIndex::new(&json_data));
         Self {
             asset_path,
             json_data,
+            index,
         }
     }
 
+    pub fn json_data(&self) -> &LdtkJson {
+        &self.json_data
+    }
+
+    pub fn json_data_mut(&mut self) -> &mut LdtkJson {
+        self.index.take();
+        &mut self.json_data
+    }
+
+    pub fn find_level_by_iid_mut(&mut self, level_iid: &str) -> Option<&mut Level> {
+        self.index.take();
+        self.json_data
+            .levels
+            .iter_mut()
+            .chain(
+                self.json_data
+                    .worlds
+                    .iter_mut()
+                    .flat_map(|world| world.levels.iter_mut()),
+            )
+            .find(|level| level.iid == level_iid)
+    }
+
+    /// `region` holds the inclusive bounds of the changed cells
+    pub fn refresh_auto_layers(&mut self, level_iid: &str, source_layer_iid: &str, region: IRect) {
+        let LdtkJson {
+            defs,
+            levels,
+            worlds,
+            ..
+        } = &mut self.json_data;
+
+        if let Some(level) = levels
+            .iter_mut()
+            .chain(worlds.iter_mut().flat_map(|world| world.levels.iter_mut()))
+            .find(|level| level.iid == level_iid)
+        {
+            refresh_dependent_auto_layers(level, defs, source_layer_iid, region);
+        }
+    }
+
+    pub fn add_level(&mut self, builder: LevelBuilder) -> Result<&Level, LevelBuilderError> {
+        let world_index = match builder.world_iid() {
+            Some(world_iid) => self
+                .json_data
+                .worlds
+                .iter()
+                .position(|world| world.iid == world_iid)
+                .ok_or_else(|| LevelBuilderError::WorldNotFound(world_iid.to_string()))?,
+            None if self.json_data.worlds.is_empty() => return Err(LevelBuilderError::NoWorld),
+            None => 0,
+        };
+
+        let level_uid = self.json_data.next_uid;
+        self.json_data.next_uid += 1;
+        let level = builder.build(
+            &self.json_data.defs,
+            level_uid,
+            &self.json_data.default_level_bg_color,
+        );
+
+        self.index.take();
+        let levels = &mut self.json_data.worlds[world_index].levels;
+        levels.push(level);
+        Ok(&levels[levels.len() - 1])
+    }
+
     pub fn get_tileset_defs<'a>(
         &'a self,
         tileset_uid_set: &HashSet<i64>,
     ) -> Vec<&'a TilesetDefinition> {
-        self.json_data
-            .defs
-            .tilesets
+        tileset_uid_set
             .iter()
-            .filter(|&tileset| tileset_uid_set.contains(&tileset.uid))
+            .filter_map(|&tileset_uid| self.tileset_def(tileset_uid))
             .collect::<Vec<_>>()
     }
 
+    pub fn level_by_iid(&self, level_iid: &str) -> Option<&Level> {
+        self.index()
+            .levels_by_iid
+            .get(level_iid)?
+            .get(&self.json_data)
+    }
+
+    pub fn level_by_uid(&self, level_uid: i64) -> Option<&Level> {
+        self.index()
+            .levels_by_uid
+            .get(&level_uid)?
+            .get(&self.json_data)
+    }
+
+    pub fn level_by_identifier(&self, level_identifier: &str) -> Option<&Level> {
+        self.index()
+            .levels_by_identifier
+            .get(level_identifier)?
+            .get(&self.json_data)
+    }
+
+    /// External levels aren't indexed
+    pub fn layer_instance_by_iid(&self, layer_iid: &str) -> Option<&LayerInstance> {
+        let (level_index, layer_index) = self.index().layer_instances_by_iid.get(layer_iid)?;
+        level_index.get_layer_instance(&self.json_data, *layer_index)
+    }
+
+    /// External levels aren't indexed
+    pub fn entity_instance_by_iid(&self, entity_iid: &str) -> Option<&EntityInstance> {
+        let (level_index, layer_index, entity_index) =
+            self.index().entity_instances_by_iid.get(entity_iid)?;
+        level_index.get_entity_instance(&self.json_data, *layer_index, *entity_index)
+    }
+
+    pub fn tileset_def(&self, tileset_uid: i64) -> Option<&TilesetDefinition> {
+        let index = self.index().tileset_defs_by_uid.get(&tileset_uid)?;
+        self.json_data.defs.tilesets.get(*index)
+    }
+
+    pub fn layer_def(&self, layer_def_uid: i64) -> Option<&LayerDefinition> {
+        let index = self.index().layer_defs_by_uid.get(&layer_def_uid)?;
+        self.json_data.defs.layers.get(*index)
+    }
+
+    pub fn entity_def(&self, entity_def_uid: i64) -> Option<&EntityDefinition> {
+        let index = self.index().entity_defs_by_uid.get(&entity_def_uid)?;
+        self.json_data.defs.entities.get(*index)
+    }
+
+    pub fn enum_def(&self, enum_def_uid: i64) -> Option<&EnumDefinition> {
+        self.index()
+            .enum_defs_by_uid
+            .get(&enum_def_uid)?
+            .get(&self.json_data)
+    }
+
+    pub fn field_def(&self, field_def_uid: i64) -> Option<&FieldDefinition> {
+        self.index()
+            .field_defs_by_uid
+            .get(&field_def_uid)?
+            .get(&self.json_data)
+    }
+
     pub fn get_asset_rel_path(&self, rel_path: &str) -> Option<String> {
         Some(
             self.asset_path
@@ -40,10 +180,295 @@ impl LDtkProject {
                 .to_string(),
         )
     }
+
+    /// Layer instances are left out when the project saves levels separately, like LDtk does
+    pub fn to_json_bytes(&self) -> Result<Vec<u8>, serde_json::Error> {
+        if !self.json_data.external_levels {
+            return to_ldtk_json_bytes(&self.json_data, self.json_data.minify_json);
+        }
+
+        let mut json_data = self.json_data.clone();
+        json_data
+            .levels
+            .iter_mut()
+            .chain(
+                json_data
+                    .worlds
+                    .iter_mut()
+                    .flat_map(|world| world.levels.iter_mut()),
+            )
+            .for_each(|level| level.layer_instances = None);
+
+        to_ldtk_json_bytes(&json_data, json_data.minify_json)
+    }
+
+    /// Also writes the `.ldtkl` file of every external level whose layers are loaded
+    pub fn save(&self, project_path: impl AsRef<Path>) -> Result<(), LDtkProjectSaverError> {
+        let project_path = project_path.as_ref();
+
+        if self.json_data.external_levels {
+            let project_dir = project_path.parent().unwrap_or(Path::new(""));
+
+            for level in self.iter_all_levels() {
+                let (Some(external_rel_path), Some(_)) =
+                    (&level.external_rel_path, &level.layer_instances)
+                else {
+                    continue;
+                };
+
+                let level_path = project_dir.join(external_rel_path);
+                if let Some(level_dir) = level_path.parent() {
+                    fs::create_dir_all(level_dir)?;
+                }
+                fs::write(
+                    level_path,
+                    to_ldtk_json_bytes(level, self.json_data.minify_json)?,
+                )?;
+            }
+        }
+
+        fs::write(project_path, self.to_json_bytes()?)?;
+
+        Ok(())
+    }
+
+    fn index(&self) -> &LDtkProjectIndex {
+        self.index
+            .get_or_init(|| LDtkProjectIndex::new(&self.json_data))
+    }
+
+    fn world_level(&self, level_index: LevelIndex) -> Option<(&World, &Level)> {
+        let world = self.json_data.worlds.get(level_index.world?)?;
+        Some((world, level_index.get(&self.json_data)?))
+    }
+
+    fn iter_all_levels(&self) -> impl Iterator<Item = &Level> {
+        self.json_data.levels.iter().chain(self.iter_world_levels())
+    }
 }
 
 impl LevelAccessor for LDtkProject {
     fn worlds(&self) -> &[World] {
         &self.json_data.worlds
     }
+
+    fn find_world_level_by_iid(&self, level_iid: &str) -> Option<(&World, &Level)> {
+        self.world_level(*self.index().levels_by_iid.get(level_iid)?)
+    }
+
+    fn find_world_level_by_identifier(
+        &self,
+        world_identifier: Option<&str>,
+        level_identifier: &str,
+    ) -> Option<(&World, &Level)> {
+        let is_in_world = |world: &World| {
+            world_identifier.is_none_or(|world_identifier| world.identifier == world_identifier)
+        };
+        let level_index = self.index().levels_by_identifier.get(level_identifier)?;
+        match self.world_level(*level_index) {
+            Some((world, level)) if is_in_world(world) => Some((world, level)),
+            // Identifiers are only unique in a world, the index holds the first level using it
+            _ => self.find_world_level(|world, level| {
+                is_in_world(world) && level.identifier == level_identifier
+            }),
+        }
+    }
+
+    fn find_world_level_by_uid(&self, level_uid: i64) -> Option<(&World, &Level)> {
+        let level_index = self.index().levels_by_uid.get(&level_uid)?;
+        self.world_level(*level_index)
+            .or_else(|| self.find_world_level(|_, level| level.uid == level_uid))
+    }
+}
+
+fn to_ldtk_json_bytes<T: Serialize>(value: &T, minify: bool) -> Result<Vec<u8>, serde_json::Error> {
+    let mut bytes = Vec::new();
+    if minify {
+        let formatter = LDtkJsonFormatter(CompactFormatter);
+        value.serialize(&mut serde_json::Serializer::with_formatter(
+            &mut bytes, formatter,
+        ))?;
+    } else {
+        let formatter = LDtkJsonFormatter(PrettyFormatter::with_indent(b"\t"));
+        value.serialize(&mut serde_json::Serializer::with_formatter(
+            &mut bytes, formatter,
+        ))?;
+    }
+    Ok(bytes)
+}
+
+/// Writes whole floats as `1` rather than `1.0`, like LDtk
+struct LDtkJsonFormatter<F>(F);
+
+impl<F: Formatter> Formatter for LDtkJsonFormatter<F> {
+    fn write_f64<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f64) -> io::Result<()> {
+        if value.fract() == 0. && value.abs() < (1u64 << 53) as f64 {
+            self.0.write_i64(writer, value as i64)
+        } else {
+            self.0.write_f64(writer, value)
+        }
+    }
+
+    fn write_f32<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f32) -> io::Result<()> {
+        self.write_f64(writer, value as f64)
+    }
+
+    fn begin_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.begin_array(writer)
+    }
+
+    fn end_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.end_array(writer)
+    }
+
+    fn begin_array_value<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.0.begin_array_value(writer, first)
+    }
+
+    fn end_array_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.end_array_value(writer)
+    }
+
+    fn begin_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.begin_object(writer)
+    }
+
+    fn end_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.end_object(writer)
+    }
+
+    fn begin_object_key<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.0.begin_object_key(writer, first)
+    }
+
+    fn end_object_key<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.end_object_key(writer)
+    }
+
+    fn begin_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.begin_object_value(writer)
+    }
+
+    fn end_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.end_object_value(writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ldtk::test_fixtures::{SAMPLE_PROJECT, sample_project},
+        resources::prelude::LevelSelection,
+    };
+
+    fn assert_saved_like_sample(bytes: &[u8]) {
+        let saved: serde_json::Value = serde_json::from_slice(bytes).unwrap();
+        let original: serde_json::Value = serde_json::from_str(SAMPLE_PROJECT).unwrap();
+        assert_eq!(saved, original);
+    }
+
+    #[test]
+    fn saving_keeps_every_field() {
+        let project = sample_project();
+        assert_saved_like_sample(&project.to_json_bytes().unwrap());
+    }
+
+    #[test]
+    fn minified_saving_keeps_every_field() {
+        let mut project = sample_project();
+        project.json_data_mut().minify_json = true;
+        let bytes = project.to_json_bytes().unwrap();
+        assert!(!bytes.contains(&b'\n'));
+
+        project.json_data_mut().minify_json = false;
+        let mut saved: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        saved["minifyJson"] = false.into();
+        assert_saved_like_sample(&serde_json::to_vec(&saved).unwrap());
+    }
+
+    #[test]
+    fn saving_writes_whole_floats_as_integers() {
+        let bytes = to_ldtk_json_bytes(&[1.0, 0.5, -3.0], true).unwrap();
+        assert_eq!(bytes, b"[1,0.5,-3]");
+    }
+
+    fn selected_level_iid(
+        project: &LDtkProject,
+        level_selection: LevelSelection,
+    ) -> Option<String> {
+        let (_, levels) = project.find_world_levels(&level_selection, None)?;
+        Some(levels.first()?.iid.clone())
+    }
+
+    #[test]
+    fn lookups_follow_json_data_changes() {
+        let mut project = sample_project();
+        let level_iid = project.json_data().worlds[0].levels[1].iid.clone();
+        assert_eq!(
+            selected_level_iid(&project, LevelSelection::by_uid(4)),
+            Some(level_iid.clone())
+        );
+
+        let level = &mut project.json_data_mut().worlds[0].levels[1];
+        level.identifier = "Renamed".to_string();
+        level.uid = 40;
+
+        assert_eq!(
+            selected_level_iid(&project, LevelSelection::by_identifier("Renamed")),
+            Some(level_iid.clone())
+        );
+        assert_eq!(
+            selected_level_iid(&project, LevelSelection::by_uid(40)),
+            Some(level_iid.clone())
+        );
+        assert_eq!(
+            selected_level_iid(&project, LevelSelection::by_uid(4)),
+            None
+        );
+        assert_eq!(
+            selected_level_iid(&project, LevelSelection::by_identifier("Level_1")),
+            None
+        );
+        assert_eq!(
+            project
+                .find_world_level_by_iid(&level_iid)
+                .unwrap()
+                .1
+                .identifier,
+            "Renamed"
+        );
+    }
+
+    #[test]
+    fn lookups_find_added_levels() {
+        let mut project = sample_project();
+        let layer_def = project.json_data().defs.layers[0].clone();
+        let builder = LevelBuilder::new("Generated", &layer_def, UVec2::new(6, 4), [])
+            .unwrap()
+            .world_position(IVec2::new(0, 64));
+        let level = project.add_level(builder).unwrap();
+        let (level_iid, level_uid) = (level.iid.clone(), level.uid);
+
+        assert_eq!(
+            selected_level_iid(&project, LevelSelection::by_identifier("Generated")),
+            Some(level_iid.clone())
+        );
+        assert_eq!(
+            selected_level_iid(&project, LevelSelection::by_uid(level_uid)),
+            Some(level_iid.clone())
+        );
+        assert_eq!(
+            selected_level_iid(&project, LevelSelection::by_iid(&level_iid)),
+            Some(level_iid)
+        );
+    }
 }
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use crate::ldtk::prelude::{
+    EntityInstance, EnumDefinition, FieldDefinition, LayerInstance, LdtkJson, Level,
+};
+
+#[derive(Clone, Copy)]
+pub(crate) struct LevelIndex {
+    pub(crate) world: Option<usize>,
+    pub(crate) level: usize,
+}
+
+#[derive(Default)]
+pub(crate) struct LDtkProjectIndex {
+    pub(crate) levels_by_iid: HashMap<String, LevelIndex>,
+    pub(crate) levels_by_uid: HashMap<i64, LevelIndex>,
+    pub(crate) levels_by_identifier: HashMap<String, LevelIndex>,
+    pub(crate) layer_instances_by_iid: HashMap<String, (LevelIndex, usize)>,
+    pub(crate) entity_instances_by_iid: HashMap<String, (LevelIndex, usize, usize)>,
+    pub(crate) tileset_defs_by_uid: HashMap<i64, usize>,
+    pub(crate) layer_defs_by_uid: HashMap<i64, usize>,
+    pub(crate) entity_defs_by_uid: HashMap<i64, usize>,
+    pub(crate) enum_defs_by_uid: HashMap<i64, EnumDefIndex>,
+    pub(crate) field_defs_by_uid: HashMap<i64, FieldDefIndex>,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum EnumDefIndex {
+    Project(usize),
+    External(usize),
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum FieldDefIndex {
+    Level(usize),
+    Entity(usize, usize),
+}
+
+impl LDtkProjectIndex {
+    pub(crate) fn new(json_data: &LdtkJson) -> Self {
+        let mut index = Self::default();
+
+        let root_levels = json_data
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(level_index, level)| {
+                let level_index = LevelIndex {
+                    world: None,
+                    level: level_index,
+                };
+                (level_index, level)
+            });
+        let world_levels = json_data
+            .worlds
+            .iter()
+            .enumerate()
+            .flat_map(|(world_index, world)| {
+                world
+                    .levels
+                    .iter()
+                    .enumerate()
+                    .map(move |(level_index, level)| {
+                        let level_index = LevelIndex {
+                            world: Some(world_index),
+                            level: level_index,
+                        };
+                        (level_index, level)
+                    })
+            });
+
+        // The first level wins, like a linear search would
+        for (level_index, level) in root_levels.chain(world_levels) {
+            index
+                .levels_by_iid
+                .entry(level.iid.clone())
+                .or_insert(level_index);
+            index.levels_by_uid.entry(level.uid).or_insert(level_index);
+            index
+                .levels_by_identifier
+                .entry(level.identifier.clone())
+                .or_insert(level_index);
+
+            for (layer_index, layer) in level.layer_instances.iter().flatten().enumerate() {
+                index
+                    .layer_instances_by_iid
+                    .entry(layer.iid.clone())
+                    .or_insert((level_index, layer_index));
+
+                for (entity_index, entity_instance) in layer.entity_instances.iter().enumerate() {
+                    index
+                        .entity_instances_by_iid
+                        .entry(entity_instance.iid.clone())
+                        .or_insert((level_index, layer_index, entity_index));
+                }
+            }
+        }
+
+        let defs = &json_data.defs;
+        index.tileset_defs_by_uid = uid_positions(defs.tilesets.iter().map(|def| def.uid));
+        index.layer_defs_by_uid = uid_positions(defs.layers.iter().map(|def| def.uid));
+        index.entity_defs_by_uid = uid_positions(defs.entities.iter().map(|def| def.uid));
+
+        for (enum_index, enum_def) in defs.enums.iter().enumerate() {
+            index
+                .enum_defs_by_uid
+                .entry(enum_def.uid)
+                .or_insert(EnumDefIndex::Project(enum_index));
+        }
+        for (enum_index, enum_def) in defs.external_enums.iter().enumerate() {
+            index
+                .enum_defs_by_uid
+                .entry(enum_def.uid)
+                .or_insert(EnumDefIndex::External(enum_index));
+        }
+
+        for (field_index, field_def) in defs.level_fields.iter().enumerate() {
+            index
+                .field_defs_by_uid
+                .entry(field_def.uid)
+                .or_insert(FieldDefIndex::Level(field_index));
+        }
+        for (entity_index, entity_def) in defs.entities.iter().enumerate() {
+            for (field_index, field_def) in entity_def.field_defs.iter().enumerate() {
+                index
+                    .field_defs_by_uid
+                    .entry(field_def.uid)
+                    .or_insert(FieldDefIndex::Entity(entity_index, field_index));
+            }
+        }
+
+        index
+    }
+}
+
+impl LevelIndex {
+    pub(crate) fn get(self, json_data: &LdtkJson) -> Option<&Level> {
+        match self.world {
+            Some(world) => json_data.worlds.get(world)?.levels.get(self.level),
+            None => json_data.levels.get(self.level),
+        }
+    }
+
+    pub(crate) fn get_layer_instance(
+        self,
+        json_data: &LdtkJson,
+        layer_index: usize,
+    ) -> Option<&LayerInstance> {
+        self.get(json_data)?
+            .layer_instances
+            .as_ref()?
+            .get(layer_index)
+    }
+
+    pub(crate) fn get_entity_instance(
+        self,
+        json_data: &LdtkJson,
+        layer_index: usize,
+        entity_index: usize,
+    ) -> Option<&EntityInstance> {
+        self.get_layer_instance(json_data, layer_index)?
+            .entity_instances
+            .get(entity_index)
+    }
+}
+
+impl EnumDefIndex {
+    pub(crate) fn get(self, json_data: &LdtkJson) -> Option<&EnumDefinition> {
+        match self {
+            EnumDefIndex::Project(index) => json_data.defs.enums.get(index),
+            EnumDefIndex::External(index) => json_data.defs.external_enums.get(index),
+        }
+    }
+}
+
+impl FieldDefIndex {
+    pub(crate) fn get(self, json_data: &LdtkJson) -> Option<&FieldDefinition> {
+        match self {
+            FieldDefIndex::Level(index) => json_data.defs.level_fields.get(index),
+            FieldDefIndex::Entity(entity_index, field_index) => json_data
+                .defs
+                .entities
+                .get(entity_index)?
+                .field_defs
+                .get(field_index),
+        }
+    }
+}
+
+fn uid_positions(uids: impl Iterator<Item = i64>) -> HashMap<i64, usize> {
+    let mut positions = HashMap::new();
+    for (index, uid) in uids.enumerate() {
+        positions.entry(uid).or_insert(index);
+    }
+    positions
+}
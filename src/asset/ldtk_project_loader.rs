@@ -7,7 +7,7 @@ use crate::asset::{ldtk_project::LDtkProject, ldtk_project_loader_error::LDtkPro
 use crate::ldtk::prelude::LdtkJson;
 
 #[derive(Default)]
-pub(crate) struct LDtkProjectLoader;
+pub struct LDtkProjectLoader;
 
 impl AssetLoader for LDtkProjectLoader {
     type Asset = LDtkProject;
@@ -0,0 +1,35 @@
+use bevy::asset::{
+    AsyncWriteExt,
+    io::Writer,
+    saver::{AssetSaver, SavedAsset},
+};
+
+use crate::asset::{
+    ldtk_project::LDtkProject, ldtk_project_loader::LDtkProjectLoader,
+    ldtk_project_saver_error::LDtkProjectSaverError,
+};
+
+/// Writes the project file only, see [`LDtkProject::save`] for external levels
+#[derive(Default)]
+pub struct LDtkProjectSaver;
+
+impl AssetSaver for LDtkProjectSaver {
+    type Asset = LDtkProject;
+
+    type Settings = ();
+
+    type OutputLoader = LDtkProjectLoader;
+
+    type Error = LDtkProjectSaverError;
+
+    async fn save(
+        &self,
+        writer: &mut Writer,
+        asset: SavedAsset<'_, Self::Asset>,
+        _: &Self::Settings,
+    ) -> Result<(), Self::Error> {
+        let bytes = asset.to_json_bytes()?;
+        writer.write_all(&bytes).await?;
+        Ok(())
+    }
+}
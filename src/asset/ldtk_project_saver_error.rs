@@ -0,0 +1,10 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LDtkProjectSaverError {
+    #[error("encountered io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("serialize ldtk project json failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
@@ -1,17 +1,34 @@
+mod ldtk_external_level;
+mod ldtk_external_level_loader;
+mod ldtk_external_level_loader_error;
 mod ldtk_project;
+mod ldtk_project_index;
 mod ldtk_project_loader;
 mod ldtk_project_loader_error;
+mod ldtk_project_saver;
+mod ldtk_project_saver_error;
 
 pub mod prelude {
-    use crate::asset::{ldtk_project, ldtk_project_loader, ldtk_project_loader_error};
+    use crate::asset::{
+        ldtk_external_level, ldtk_external_level_loader, ldtk_external_level_loader_error,
+        ldtk_project, ldtk_project_loader, ldtk_project_loader_error, ldtk_project_saver,
+        ldtk_project_saver_error,
+    };
     use bevy::prelude::*;
 
-    pub(crate) use ldtk_project::LDtkProject;
-    pub(crate) use ldtk_project_loader::LDtkProjectLoader;
+    pub use ldtk_external_level::LDtkExternalLevel;
+    pub use ldtk_external_level_loader::LDtkExternalLevelLoader;
+    pub use ldtk_external_level_loader_error::LDtkExternalLevelLoaderError;
+    pub use ldtk_project::LDtkProject;
+    pub use ldtk_project_loader::LDtkProjectLoader;
     pub use ldtk_project_loader_error::LDtkProjectLoaderError;
+    pub use ldtk_project_saver::LDtkProjectSaver;
+    pub use ldtk_project_saver_error::LDtkProjectSaverError;
 
     pub(crate) fn plugin(app: &mut App) {
         app.init_asset::<LDtkProject>()
-            .init_asset_loader::<LDtkProjectLoader>();
+            .init_asset_loader::<LDtkProjectLoader>()
+            .init_asset::<LDtkExternalLevel>()
+            .init_asset_loader::<LDtkExternalLevelLoader>();
     }
 }
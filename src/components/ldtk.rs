@@ -1,10 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use bevy::{asset::UntypedAssetId, math::I64Vec2, prelude::*};
+use bevy::{
+    asset::UntypedAssetId,
+    ecs::{lifecycle::HookContext, world::DeferredWorld},
+    prelude::*,
+};
 
 use crate::{
-    asset::prelude::LDtkProject,
-    ldtk::prelude::{Level, World},
+    asset::prelude::{LDtkExternalLevel, LDtkProject},
+    events::prelude::LevelDespawned,
+    ldtk::prelude::{LayerDefinition, LayerInstance, Level, TileInstance, World},
 };
 
 #[derive(Component)]
@@ -23,31 +28,316 @@ impl From<&LDtkProjectHandle> for UntypedAssetId {
     }
 }
 
+/// Prefers the level holding the `LDtkPlayer`, the last value is kept while no level is
+/// spawned
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct CurrentLevel {
+    pub world_iid: String,
+    pub level_iid: String,
+    pub identifier: String,
+    /// Relative to the `LDtkProjectHandle` entity, in Bevy units
+    pub bounds: Rect,
+}
+
 #[derive(Component)]
-#[require(Transform, Visibility)]
+#[require(Transform, Visibility, LDtkLevelLoads)]
 pub struct LDtkWorld {
     pub world: World,
-    pub level: Level,
+    /// Levels are spawned and despawned to follow this set
+    pub level_iids: HashSet<String>,
     pub tileset_handles: HashMap<i64, (usize, Handle<Image>, Handle<TextureAtlasLayout>)>,
+    pub opaque_tiles: HashMap<i64, Vec<bool>>,
+    pub background_handles: HashMap<String, Handle<Image>>,
 }
 
+impl LDtkWorld {
+    pub fn new(world: World, level_iids: HashSet<String>) -> Self {
+        Self {
+            world,
+            level_iids,
+            tileset_handles: HashMap::new(),
+            opaque_tiles: HashMap::new(),
+            background_handles: HashMap::new(),
+        }
+    }
+
+    pub fn level(&self, level_iid: &str) -> Option<&Level> {
+        self.world
+            .levels
+            .iter()
+            .find(|level| level.iid == level_iid)
+    }
+
+    pub fn level_mut(&mut self, level_iid: &str) -> Option<&mut Level> {
+        self.world
+            .levels
+            .iter_mut()
+            .find(|level| level.iid == level_iid)
+    }
+
+    pub fn levels(&self) -> impl Iterator<Item = &Level> {
+        self.world
+            .levels
+            .iter()
+            .filter(|level| self.level_iids.contains(&level.iid))
+    }
+
+    /// External layers are loaded again from their file once selected
+    pub(crate) fn free_unselected_levels(&mut self) {
+        let level_iids = &self.level_iids;
+        self.world
+            .levels
+            .iter_mut()
+            .filter(|level| level.external_rel_path.is_some() && !level_iids.contains(&level.iid))
+            .for_each(|level| level.layer_instances = None);
+        self.background_handles
+            .retain(|level_iid, _| level_iids.contains(level_iid));
+    }
+
+    pub fn level_with_layer(&self, layer_iid: &str) -> Option<&Level> {
+        self.levels()
+            .find(|level| level.layer_instance(layer_iid).is_some())
+    }
+
+    pub fn layer_instance(&self, layer_iid: &str) -> Option<&LayerInstance> {
+        self.levels()
+            .find_map(|level| level.layer_instance(layer_iid))
+    }
+}
+
+#[derive(Component, Default)]
+pub struct LDtkLevelLoads {
+    pub(crate) pending: HashMap<String, Handle<LDtkExternalLevel>>,
+    pub(crate) failed: HashSet<String>,
+}
+
+impl LDtkLevelLoads {
+    pub fn is_loading(&self, level_iid: &str) -> bool {
+        self.pending.contains_key(level_iid)
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// Alpha before `LDtkDepthDisplayMode::Fade` was applied
+#[derive(Component)]
+pub(crate) struct LDtkBaseAlpha(pub(crate) f32);
+
+/// The entity whose position switches levels, see `LDtkLevelTransitionPlugin`
+#[derive(Component)]
+pub struct LDtkPlayer;
+
+/// The entity levels are streamed around, see `LDtkLevelStreaming`
+#[derive(Component)]
+pub struct LDtkStreamingTarget;
+
 #[derive(Component)]
 #[require(Transform, Visibility)]
+#[component(on_despawn = despawn_ldtk_level)]
 pub struct LDtkLevel(pub String);
 
+fn despawn_ldtk_level(mut world: DeferredWorld, context: HookContext) {
+    let Some(ldtk_level) = world.get::<LDtkLevel>(context.entity) else {
+        return;
+    };
+    let level_iid = ldtk_level.0.clone();
+    world.trigger(LevelDespawned {
+        entity: context.entity,
+        level_iid,
+    });
+}
+
 #[derive(Component)]
 #[require(Transform, Visibility)]
 pub struct LDtkLayer(pub String);
 
+#[derive(Component, Debug, Clone)]
+pub struct LDtkLayerDef(pub LayerDefinition);
+
+#[derive(Component, Debug, Clone)]
+pub struct LDtkParallax {
+    /// From -1 to 1
+    pub factor: Vec2,
+    pub scaling: bool,
+    pub(crate) rest_translation: Vec2,
+    pub(crate) level_center: Vec2,
+}
+
+#[derive(Component)]
+pub struct LDtkLevelBackground;
+
 #[derive(Component)]
 #[require(Transform, Visibility)]
+#[component(on_insert = store_ldtk_tile, on_replace = unstore_ldtk_tile)]
 pub struct LDtkTile;
 
+fn store_ldtk_tile(mut world: DeferredWorld, context: HookContext) {
+    let Some((layer_entity, grid_coord, stack_index)) = ldtk_tile_key(&world, context.entity)
+    else {
+        return;
+    };
+    let Some(mut ldtk_tile_storage) = world.get_mut::<LDtkTileStorage>(layer_entity) else {
+        return;
+    };
+    let replaced_tile_entity = ldtk_tile_storage
+        .tiles
+        .entry(grid_coord)
+        .or_default()
+        .insert(stack_index, context.entity);
+
+    if let Some(replaced_tile_entity) =
+        replaced_tile_entity.filter(|tile_entity| *tile_entity != context.entity)
+    {
+        world.commands().entity(replaced_tile_entity).try_despawn();
+    }
+}
+
+fn unstore_ldtk_tile(mut world: DeferredWorld, context: HookContext) {
+    let Some((layer_entity, grid_coord, stack_index)) = ldtk_tile_key(&world, context.entity)
+    else {
+        return;
+    };
+    let Some(mut ldtk_tile_storage) = world.get_mut::<LDtkTileStorage>(layer_entity) else {
+        return;
+    };
+    let Some(stack) = ldtk_tile_storage.tiles.get_mut(&grid_coord) else {
+        return;
+    };
+
+    if stack.get(&stack_index) == Some(&context.entity) {
+        stack.remove(&stack_index);
+    }
+    if stack.is_empty() {
+        ldtk_tile_storage.tiles.remove(&grid_coord);
+    }
+}
+
+fn ldtk_tile_key(world: &DeferredWorld, tile_entity: Entity) -> Option<(Entity, IVec2, usize)> {
+    let entity = world.get_entity(tile_entity).ok()?;
+    Some((
+        entity.get::<ChildOf>()?.parent(),
+        entity.get::<LDtkGridCoord>()?.0,
+        entity.get::<LDtkStackIndex>()?.0,
+    ))
+}
+
+/// Kept in sync for tiles carrying `ChildOf`, `LDtkGridCoord` and `LDtkStackIndex` when
+/// `LDtkTile` is inserted. A tile stored over another one despawns it.
+#[derive(Component, Default, Debug)]
+pub struct LDtkTileStorage {
+    tiles: HashMap<IVec2, BTreeMap<usize, Entity>>,
+}
+
+impl LDtkTileStorage {
+    pub fn get(&self, grid_coord: &LDtkGridCoord, stack_index: usize) -> Option<Entity> {
+        self.tiles.get(&grid_coord.0)?.get(&stack_index).copied()
+    }
+
+    /// From the bottom of the stack
+    pub fn stack<'a>(
+        &'a self,
+        grid_coord: &LDtkGridCoord,
+    ) -> impl Iterator<Item = Entity> + use<'a> {
+        self.tiles
+            .get(&grid_coord.0)
+            .into_iter()
+            .flat_map(|stack| stack.values().copied())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (LDtkGridCoord, usize, Entity)> + '_ {
+        self.tiles.iter().flat_map(|(grid_coord, stack)| {
+            stack.iter().map(|(stack_index, tile_entity)| {
+                (LDtkGridCoord(*grid_coord), *stack_index, *tile_entity)
+            })
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.values().map(BTreeMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+}
+
+#[derive(Component)]
+#[require(Transform, Visibility)]
+pub struct LDtkEntity(pub String);
+
+pub const LDTK_TILE_CHUNK_SIZE: i32 = 32;
+
+#[derive(Component)]
+pub struct LDtkTileChunks {
+    pub(crate) chunks: HashMap<IVec2, (Entity, Handle<Mesh>)>,
+    pub(crate) texture_atlas_layout: Handle<TextureAtlasLayout>,
+}
+
+impl LDtkTileChunks {
+    pub fn chunk_coord(grid_coord: &LDtkGridCoord) -> IVec2 {
+        grid_coord.div_euclid(IVec2::splat(LDTK_TILE_CHUNK_SIZE))
+    }
+
+    pub fn chunk_at(&self, grid_coord: &LDtkGridCoord) -> Option<Entity> {
+        self.chunks
+            .get(&Self::chunk_coord(grid_coord))
+            .map(|(chunk_entity, _)| *chunk_entity)
+    }
+}
+
+#[derive(Component)]
+#[require(Transform, Visibility)]
+pub struct LDtkTileChunk {
+    pub(crate) tiles: HashMap<IVec2, Vec<TileInstance>>,
+}
+
+impl LDtkTileChunk {
+    /// From the bottom of the stack
+    pub fn tiles(&self, grid_coord: &LDtkGridCoord) -> &[TileInstance] {
+        self.tiles
+            .get(&grid_coord.0)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Component, Deref, Eq, PartialEq, Reflect)]
 pub struct LDtkGridCoord(pub IVec2);
 
 #[derive(Component, Deref, Eq, PartialEq, Reflect)]
 pub struct BevyGridCoord(pub IVec2);
 
-#[derive(Component)]
-pub(crate) struct Spawned;
+/// 0 is the bottom tile
+#[derive(Component, Deref, Eq, PartialEq, Reflect)]
+pub struct LDtkStackIndex(pub usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ldtk::test_fixtures::sample_json;
+
+    #[test]
+    fn unselected_external_levels_are_freed() {
+        let mut world = sample_json().worlds[0].clone();
+        world.levels[0].external_rel_path = Some("external/Level_0.ldtkl".to_string());
+        world.levels[1].external_rel_path = Some("external/Level_1.ldtkl".to_string());
+        let kept_level_iid = world.levels[0].iid.clone();
+        let freed_level_iid = world.levels[1].iid.clone();
+
+        let mut ldtk_world = LDtkWorld {
+            background_handles: HashMap::from([
+                (kept_level_iid.clone(), Handle::default()),
+                (freed_level_iid.clone(), Handle::default()),
+            ]),
+            ..LDtkWorld::new(world, HashSet::from([kept_level_iid.clone()]))
+        };
+        ldtk_world.free_unselected_levels();
+
+        assert!(ldtk_world.world.levels[0].layer_instances.is_some());
+        assert!(ldtk_world.world.levels[1].layer_instances.is_none());
+        assert!(ldtk_world.background_handles.contains_key(&kept_level_iid));
+        assert!(!ldtk_world.background_handles.contains_key(&freed_level_iid));
+    }
+}
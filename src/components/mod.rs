@@ -3,8 +3,12 @@ mod ldtk;
 pub mod prelude {
     use crate::components::ldtk;
 
-    pub(crate) use ldtk::Spawned;
     pub use ldtk::{
-        BevyGridCoord, LDtkGridCoord, LDtkLayer, LDtkLevel, LDtkProjectHandle, LDtkTile, LDtkWorld,
+        BevyGridCoord, CurrentLevel, LDTK_TILE_CHUNK_SIZE, LDtkEntity, LDtkGridCoord, LDtkLayer,
+        LDtkLayerDef, LDtkLevel, LDtkLevelBackground, LDtkLevelLoads, LDtkParallax, LDtkPlayer,
+        LDtkProjectHandle, LDtkStackIndex, LDtkStreamingTarget, LDtkTile, LDtkTileChunk,
+        LDtkTileChunks, LDtkTileStorage, LDtkWorld,
     };
+
+    pub(crate) use ldtk::LDtkBaseAlpha;
 }
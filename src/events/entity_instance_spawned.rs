@@ -0,0 +1,9 @@
+use bevy::ecs::{entity::Entity, event::EntityEvent};
+
+#[derive(EntityEvent, Debug, Clone)]
+pub struct EntityInstanceSpawned {
+    pub entity: Entity,
+    pub level_iid: String,
+    pub layer_iid: String,
+    pub entity_iid: String,
+}
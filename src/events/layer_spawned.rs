@@ -0,0 +1,8 @@
+use bevy::ecs::{entity::Entity, event::EntityEvent};
+
+#[derive(EntityEvent, Debug, Clone)]
+pub struct LayerSpawned {
+    pub entity: Entity,
+    pub level_iid: String,
+    pub layer_iid: String,
+}
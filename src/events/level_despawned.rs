@@ -0,0 +1,8 @@
+use bevy::ecs::{entity::Entity, event::EntityEvent};
+
+/// Observers still see the level hierarchy
+#[derive(EntityEvent, Debug, Clone)]
+pub struct LevelDespawned {
+    pub entity: Entity,
+    pub level_iid: String,
+}
@@ -0,0 +1,8 @@
+use bevy::ecs::{entity::Entity, event::EntityEvent};
+
+/// Sent once the layers, tiles and entities of the level are spawned
+#[derive(EntityEvent, Debug, Clone)]
+pub struct LevelSpawned {
+    pub entity: Entity,
+    pub level_iid: String,
+}
@@ -0,0 +1,8 @@
+use bevy::ecs::{entity::Entity, event::EntityEvent};
+
+/// Sent before the layers of the level are spawned
+#[derive(EntityEvent, Debug, Clone)]
+pub struct LevelSpawning {
+    pub entity: Entity,
+    pub level_iid: String,
+}
@@ -0,0 +1,17 @@
+mod entity_instance_spawned;
+mod layer_spawned;
+mod level_despawned;
+mod level_spawned;
+mod level_spawning;
+
+pub mod prelude {
+    use crate::events::{
+        entity_instance_spawned, layer_spawned, level_despawned, level_spawned, level_spawning,
+    };
+
+    pub use entity_instance_spawned::EntityInstanceSpawned;
+    pub use layer_spawned::LayerSpawned;
+    pub use level_despawned::LevelDespawned;
+    pub use level_spawned::LevelSpawned;
+    pub use level_spawning::LevelSpawning;
+}
@@ -0,0 +1,627 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
+
+use bevy::math::{IRect, IVec2};
+
+use crate::ldtk::ldtk_json::{
+    AutoLayerRuleDefinition, AutoLayerRuleGroup, Checker, Definitions, FieldInstance,
+    LayerDefinition, LayerInstance, Level, TileInstance, TileMode, TilesetDefinition,
+};
+use crate::ldtk::perlin::perlin;
+
+/// Pattern value matching any non-empty cell, or only empty cells when negative
+const PATTERN_ANYTHING: i64 = 1000001;
+
+/// Flip bits and pattern reading directions tried for each rule, in the editor order
+const FLIPS: [(i64, IVec2); 4] = [
+    (0b00, IVec2::new(1, 1)),
+    (0b01, IVec2::new(-1, 1)),
+    (0b10, IVec2::new(1, -1)),
+    (0b11, IVec2::new(-1, -1)),
+];
+
+/// The editor coordinate hash. It runs on JavaScript doubles where only the bitwise operators
+/// truncate to 32 bits, so the rounding of the large product is kept.
+pub(crate) fn rand_seed_coords(seed: i64, x: i32, y: i32, max: i64) -> i64 {
+    let h = seed as f64 + x as f64 * 374761393. + y as f64 * 668265263.;
+    let h = (to_int32(h) ^ (to_int32(h) >> 13)) as f64 * 1274126177.;
+    let h = to_int32(h) ^ (to_int32(h) >> 16);
+    h.unsigned_abs() as i64 % max.max(1)
+}
+
+/// JavaScript `ToInt32`, for the integral doubles below 2^63 the hash works with
+fn to_int32(value: f64) -> i32 {
+    value as i64 as i32
+}
+
+pub(crate) struct IntGridSource {
+    c_wid: i64,
+    c_hei: i64,
+    values: Vec<i64>,
+    group_uids: HashMap<i64, i64>,
+}
+
+impl IntGridSource {
+    pub(crate) fn new(layer: &LayerInstance, layer_def: &LayerDefinition) -> Self {
+        Self {
+            c_wid: layer.c_wid,
+            c_hei: layer.c_hei,
+            values: layer.int_grid_csv.clone(),
+            group_uids: layer_def
+                .int_grid_values
+                .iter()
+                .map(|value_def| (value_def.value, value_def.group_uid))
+                .collect(),
+        }
+    }
+
+    fn value(&self, grid_coord: IVec2) -> Option<i64> {
+        if grid_coord.x < 0
+            || grid_coord.y < 0
+            || grid_coord.x as i64 >= self.c_wid
+            || grid_coord.y as i64 >= self.c_hei
+        {
+            return None;
+        }
+        let coord_id = grid_coord.x as i64 + grid_coord.y as i64 * self.c_wid;
+        Some(self.values.get(coord_id as usize).copied().unwrap_or(0))
+    }
+}
+
+/// Biome requirement mode where every required value must be present in the level
+const BIOME_REQUIREMENT_ALL: i64 = 1;
+
+/// The biome field holds a single enum value or an array of them
+pub(crate) fn level_biome_values(
+    field_instances: &[FieldInstance],
+    biome_field_uid: Option<i64>,
+) -> Vec<String> {
+    let Some(value) = biome_field_uid
+        .and_then(|uid| field_instances.iter().find(|field| field.def_uid == uid))
+        .and_then(|field| field.value.as_ref())
+    else {
+        return Vec::new();
+    };
+
+    match value {
+        serde_json::Value::String(value) => vec![value.clone()],
+        serde_json::Value::Array(values) => values
+            .iter()
+            .filter_map(|value| value.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn group_applies(
+    group: &AutoLayerRuleGroup,
+    layer: &LayerInstance,
+    biome_values: &[String],
+) -> bool {
+    if !group.active || group.is_optional && !layer.optional_rules.contains(&group.uid) {
+        return false;
+    }
+    if group.required_biome_values.is_empty() {
+        return true;
+    }
+
+    let mut required = group
+        .required_biome_values
+        .iter()
+        .map(|required| biome_values.contains(required));
+    if group.biome_requirement_mode == BIOME_REQUIREMENT_ALL {
+        required.all(|present| present)
+    } else {
+        required.any(|present| present)
+    }
+}
+
+/// In priority order, the first rule wins a cell
+fn active_rules<'a>(
+    layer_def: &'a LayerDefinition,
+    layer: &LayerInstance,
+    biome_values: &[String],
+) -> Vec<&'a AutoLayerRuleDefinition> {
+    layer_def
+        .auto_rule_groups
+        .iter()
+        .filter(|group| group_applies(group, layer, biome_values))
+        .flat_map(|group| group.rules.iter())
+        .filter(|rule| rule.active)
+        .collect()
+}
+
+/// Offsets only shift the plain modulos. Divisions truncate like the editor ones.
+fn passes_modulo(rule: &AutoLayerRuleDefinition, cx: i32, cy: i32) -> bool {
+    let x_modulo = rule.x_modulo.max(1) as i32;
+    let y_modulo = rule.y_modulo.max(1) as i32;
+
+    let x_passes = match rule.checker {
+        Checker::Horizontal => (cx + cy / y_modulo % 2) % x_modulo == 0,
+        _ => (cx - rule.x_offset as i32) % x_modulo == 0,
+    };
+    let y_passes = match rule.checker {
+        Checker::Vertical => (cy + cx / x_modulo % 2) % y_modulo == 0,
+        _ => (cy - rule.y_offset as i32) % y_modulo == 0,
+    };
+
+    x_passes && y_passes
+}
+
+fn pattern_matches(
+    rule: &AutoLayerRuleDefinition,
+    source: &IntGridSource,
+    cx: i32,
+    cy: i32,
+    direction: IVec2,
+) -> bool {
+    let size = rule.size as i32;
+    let radius = size / 2;
+
+    (0..size)
+        .flat_map(|py| (0..size).map(move |px| (px, py)))
+        .all(|(px, py)| {
+            let pattern_value = rule
+                .pattern
+                .get((px + py * size) as usize)
+                .copied()
+                .unwrap_or(0);
+            if pattern_value == 0 {
+                return true;
+            }
+
+            let grid_coord = IVec2::new(cx, cy) + direction * IVec2::new(px - radius, py - radius);
+            let Some(value) = source.value(grid_coord).or(rule.out_of_bounds_value) else {
+                return false;
+            };
+
+            let required = pattern_value.abs();
+            let matched = if required == PATTERN_ANYTHING {
+                value != 0
+            } else if required > 999 {
+                source.group_uids.get(&value) == Some(&(required / 1000 - 1))
+            } else {
+                value == required
+            };
+
+            matched == (pattern_value > 0)
+        })
+}
+
+fn tile_rects(rule: &AutoLayerRuleDefinition) -> Vec<Vec<i64>> {
+    if !rule.tile_rects_ids.is_empty() {
+        return rule.tile_rects_ids.clone();
+    }
+
+    let tile_ids = rule.tile_ids.clone().unwrap_or_default();
+    match rule.tile_mode {
+        TileMode::Single | TileMode::Unknown(_) => {
+            tile_ids.into_iter().map(|tile_id| vec![tile_id]).collect()
+        }
+        TileMode::Stamp if tile_ids.is_empty() => Vec::new(),
+        TileMode::Stamp => vec![tile_ids],
+    }
+}
+
+fn random_offset(seed: i64, cx: i32, cy: i32, min: i64, max: i64) -> i32 {
+    if max <= min {
+        return min as i32;
+    }
+    (min + rand_seed_coords(seed, cx, cy, max - min + 1)) as i32
+}
+
+fn rule_tiles(
+    rule: &AutoLayerRuleDefinition,
+    layer: &LayerInstance,
+    tileset_def: &TilesetDefinition,
+    cx: i32,
+    cy: i32,
+    flip_bits: i64,
+) -> Vec<TileInstance> {
+    let tile_rects = tile_rects(rule);
+    if tile_rects.is_empty() {
+        return Vec::new();
+    }
+
+    let seed = layer.seed + rule.uid;
+    let tile_rect = &tile_rects[rand_seed_coords(seed, cx, cy, tile_rects.len() as i64) as usize];
+
+    let grid_size = layer.grid_size as i32;
+    let columns = tileset_def.c_wid.max(1);
+    let tileset_cells = tile_rect
+        .iter()
+        .map(|&tile_id| IVec2::new((tile_id % columns) as i32, (tile_id / columns) as i32))
+        .collect::<Vec<_>>();
+    let min = tileset_cells
+        .iter()
+        .copied()
+        .reduce(IVec2::min)
+        .unwrap_or_default();
+    let max = tileset_cells
+        .iter()
+        .copied()
+        .reduce(IVec2::max)
+        .unwrap_or_default();
+    let stamp_size = max - min + IVec2::ONE;
+
+    let origin = IVec2::new(cx, cy) * grid_size
+        + IVec2::new(
+            rule.tile_x_offset as i32
+                + random_offset(
+                    seed + 1,
+                    cx,
+                    cy,
+                    rule.tile_random_x_min,
+                    rule.tile_random_x_max,
+                ),
+            rule.tile_y_offset as i32
+                + random_offset(
+                    seed + 2,
+                    cx,
+                    cy,
+                    rule.tile_random_y_min,
+                    rule.tile_random_y_max,
+                ),
+        );
+    let coord_id = cx as i64 + cy as i64 * layer.c_wid;
+
+    tile_rect
+        .iter()
+        .zip(tileset_cells)
+        .map(|(&tile_id, tileset_cell)| {
+            let offset = match rule.tile_mode {
+                TileMode::Single | TileMode::Unknown(_) => IVec2::ZERO,
+                TileMode::Stamp => {
+                    let mut cell = tileset_cell - min;
+                    if flip_bits & 0b01 != 0 {
+                        cell.x = stamp_size.x - 1 - cell.x;
+                    }
+                    if flip_bits & 0b10 != 0 {
+                        cell.y = stamp_size.y - 1 - cell.y;
+                    }
+                    let pivot_x = rule.pivot_x * (stamp_size.x - 1) as f64;
+                    let pivot_y = rule.pivot_y * (stamp_size.y - 1) as f64;
+                    IVec2::new(
+                        ((cell.x as f64 - pivot_x) * grid_size as f64) as i32,
+                        ((cell.y as f64 - pivot_y) * grid_size as f64) as i32,
+                    )
+                }
+            };
+
+            TileInstance {
+                a: rule.alpha,
+                d: vec![rule.uid, coord_id],
+                f: flip_bits,
+                px: origin + offset,
+                src: tileset_def.tile_src(tile_id),
+                t: tile_id,
+            }
+        })
+        .collect()
+}
+
+/// Applies the rules to a single cell, returning the generated tiles tagged with the
+/// priority of the rule that produced them. `perlin_passed` holds the `(rule uid, cell)` the
+/// editor already placed a perlin rule tile on, see [`perlin`].
+fn evaluate_cell(
+    rules: &[&AutoLayerRuleDefinition],
+    layer: &LayerInstance,
+    source: &IntGridSource,
+    tileset_def: &TilesetDefinition,
+    perlin_passed: &HashSet<(i64, IVec2)>,
+    cx: i32,
+    cy: i32,
+) -> Vec<(usize, TileInstance)> {
+    let mut tiles = Vec::new();
+
+    for (priority, rule) in rules.iter().enumerate() {
+        if !passes_modulo(rule, cx, cy) {
+            continue;
+        }
+
+        if rule.perlin_active
+            && !perlin_passed.contains(&(rule.uid, IVec2::new(cx, cy)))
+            && perlin(
+                layer.seed + rule.perlin_seed as i64,
+                (cx as f64 + 0.5) * rule.perlin_scale,
+                (cy as f64 + 0.5) * rule.perlin_scale,
+                rule.perlin_octaves as i64,
+            ) < 0.
+        {
+            continue;
+        }
+
+        if rule.chance <= 0.
+            || rule.chance < 1.
+                && rand_seed_coords(layer.seed + rule.uid, cx, cy, 100) as f64 >= rule.chance * 100.
+        {
+            continue;
+        }
+
+        let mut matched = false;
+        for (flip_bits, direction) in FLIPS {
+            if flip_bits & 0b01 != 0 && !rule.flip_x || flip_bits & 0b10 != 0 && !rule.flip_y {
+                continue;
+            }
+            if matched && rule.break_on_match {
+                break;
+            }
+            if pattern_matches(rule, source, cx, cy, direction) {
+                tiles.extend(
+                    rule_tiles(rule, layer, tileset_def, cx, cy, flip_bits)
+                        .into_iter()
+                        .map(|tile| (priority, tile)),
+                );
+                matched = true;
+            }
+        }
+
+        if matched && rule.break_on_match {
+            break;
+        }
+    }
+
+    tiles
+}
+
+/// `region` holds inclusive cell bounds, `None` refreshes every tile. Returns the changed
+/// cells.
+pub(crate) fn refresh_auto_layer_tiles(
+    layer: &mut LayerInstance,
+    layer_def: &LayerDefinition,
+    tileset_def: &TilesetDefinition,
+    source: &IntGridSource,
+    biome_values: &[String],
+    region: Option<IRect>,
+) -> HashSet<IVec2> {
+    let rules = active_rules(layer_def, layer, biome_values);
+    let radius = rules
+        .iter()
+        .map(|rule| rule.size as i32 / 2)
+        .max()
+        .unwrap_or_default();
+    let (min, max) = region
+        .map(|region| (region.min - radius, region.max + radius))
+        .unwrap_or((
+            IVec2::ZERO,
+            IVec2::new(layer.c_wid as i32, layer.c_hei as i32),
+        ));
+    let min = min.max(IVec2::ZERO);
+    let max = max.min(IVec2::new(layer.c_wid as i32 - 1, layer.c_hei as i32 - 1));
+    let in_region = |cell: IVec2| cell.cmpge(min).all() && cell.cmple(max).all();
+
+    let grid_size = layer.grid_size.max(1) as i32;
+    let tile_origin = |tile: &TileInstance| match tile.d.get(1) {
+        Some(coord_id) => IVec2::new(
+            (coord_id % layer.c_wid.max(1)) as i32,
+            (coord_id / layer.c_wid.max(1)) as i32,
+        ),
+        None => tile.px.div_euclid(IVec2::splat(grid_size)),
+    };
+
+    let mut changed_cells = HashSet::new();
+    let (removed_tiles, mut tiles): (Vec<_>, Vec<_>) = std::mem::take(&mut layer.auto_layer_tiles)
+        .into_iter()
+        .partition(|tile| in_region(tile_origin(tile)));
+    changed_cells.extend(
+        removed_tiles
+            .iter()
+            .map(|tile| tile.px.div_euclid(IVec2::splat(grid_size))),
+    );
+    let perlin_rule_uids = rules
+        .iter()
+        .filter(|rule| rule.perlin_active)
+        .map(|rule| rule.uid)
+        .collect::<HashSet<_>>();
+    let perlin_passed = removed_tiles
+        .iter()
+        .filter_map(|tile| {
+            let rule_uid = *tile.d.first()?;
+            perlin_rule_uids
+                .contains(&rule_uid)
+                .then(|| (rule_uid, tile_origin(tile)))
+        })
+        .collect::<HashSet<_>>();
+
+    let priorities = rules
+        .iter()
+        .enumerate()
+        .map(|(priority, rule)| (rule.uid, priority))
+        .collect::<HashMap<_, _>>();
+
+    (min.y..=max.y)
+        .flat_map(|cy| (min.x..=max.x).map(move |cx| (cx, cy)))
+        .flat_map(|(cx, cy)| {
+            evaluate_cell(&rules, layer, source, tileset_def, &perlin_passed, cx, cy)
+        })
+        .for_each(|(_, tile)| {
+            changed_cells.insert(tile.px.div_euclid(IVec2::splat(grid_size)));
+            tiles.push(tile);
+        });
+
+    // Display order: tiles of the lowest priority rules are drawn first, beneath the others
+    tiles.sort_by_key(|tile| {
+        let priority = tile
+            .d
+            .first()
+            .and_then(|rule_uid| priorities.get(rule_uid))
+            .copied()
+            .unwrap_or(usize::MAX);
+        (
+            Reverse(priority),
+            tile.d.get(1).copied().unwrap_or_default(),
+        )
+    });
+    layer.auto_layer_tiles = tiles;
+
+    changed_cells
+}
+
+/// Returns the refreshed layer iids with their changed cells
+pub(crate) fn refresh_dependent_auto_layers(
+    level: &mut Level,
+    defs: &Definitions,
+    source_layer_iid: &str,
+    region: IRect,
+) -> Vec<(String, HashSet<IVec2>)> {
+    let Some(source_layer) = level.layer_instance(source_layer_iid) else {
+        return Vec::new();
+    };
+    let source_layer_def_uid = source_layer.layer_def_uid;
+    let Some(source_layer_def) = defs
+        .layers
+        .iter()
+        .find(|layer_def| layer_def.uid == source_layer_def_uid)
+    else {
+        return Vec::new();
+    };
+    let source = IntGridSource::new(source_layer, source_layer_def);
+
+    level
+        .layer_instances
+        .iter_mut()
+        .flatten()
+        .filter_map(|layer| {
+            let layer_def = defs
+                .layers
+                .iter()
+                .find(|layer_def| layer_def.uid == layer.layer_def_uid)?;
+            let reads_source = layer.iid == source_layer_iid
+                || layer_def.auto_source_layer_def_uid == Some(source_layer_def_uid);
+            if !reads_source || layer_def.auto_rule_groups.is_empty() {
+                return None;
+            }
+
+            let tileset_def = defs
+                .tilesets
+                .iter()
+                .find(|tileset_def| Some(tileset_def.uid) == layer.tileset_def_uid)?;
+
+            let biome_values =
+                level_biome_values(&level.field_instances, layer_def.biome_field_uid);
+            let changed_cells = refresh_auto_layer_tiles(
+                layer,
+                layer_def,
+                tileset_def,
+                &source,
+                &biome_values,
+                Some(region),
+            );
+            Some((layer.iid.clone(), changed_cells))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ldtk::test_fixtures::sample_json;
+
+    type TileKey = (Vec<i64>, i64, [i32; 2], [i32; 2], i64);
+
+    fn tile_keys(tiles: &[TileInstance]) -> Vec<TileKey> {
+        tiles
+            .iter()
+            .map(|tile| {
+                (
+                    tile.d.clone(),
+                    tile.f,
+                    tile.px.into(),
+                    tile.src.into(),
+                    tile.t,
+                )
+            })
+            .collect()
+    }
+
+    fn rerun_sample_rules(region: Option<IRect>) -> (Vec<TileKey>, Vec<TileKey>) {
+        let json_data = sample_json();
+        let mut layer = json_data.worlds[0].levels[0]
+            .layer_instances
+            .as_ref()
+            .unwrap()[0]
+            .clone();
+        let layer_def = &json_data.defs.layers[0];
+        let tileset_def = &json_data.defs.tilesets[0];
+        let baked = tile_keys(&layer.auto_layer_tiles);
+
+        let source = IntGridSource::new(&layer, layer_def);
+        if region.is_none() {
+            layer.auto_layer_tiles.clear();
+        }
+        refresh_auto_layer_tiles(&mut layer, layer_def, tileset_def, &source, &[], region);
+
+        (baked, tile_keys(&layer.auto_layer_tiles))
+    }
+
+    #[test]
+    fn rand_seed_coords_follows_javascript_numbers() {
+        // Reference values evaluated with the editor expression in a JavaScript runtime
+        assert_eq!(rand_seed_coords(1234668, 2, 0, 100), 99);
+        assert_eq!(rand_seed_coords(1234568, 5, 3, 100), 89);
+        assert_eq!(rand_seed_coords(9999999, 37, 12, 65536), 54600);
+        assert_eq!(rand_seed_coords(42, 0, 0, 7), 3);
+        assert_eq!(rand_seed_coords(5000000, 120, 77, 1000), 115);
+    }
+
+    #[test]
+    fn rules_reproduce_baked_tiles() {
+        // The sample rules cover flips, checker, modulo with offset, break-on-match and
+        // out-of-bounds values
+        let (baked, refreshed) = rerun_sample_rules(None);
+        assert_eq!(refreshed, baked);
+    }
+
+    #[test]
+    fn region_refresh_keeps_baked_tiles() {
+        let region = IRect::from_corners(IVec2::new(1, 1), IVec2::new(3, 2));
+        let (baked, refreshed) = rerun_sample_rules(Some(region));
+        assert_eq!(refreshed, baked);
+    }
+
+    #[test]
+    fn perlin_rules_keep_baked_cells() {
+        let mut json_data = sample_json();
+        json_data.defs.layers[0].auto_rule_groups[0]
+            .rules
+            .iter_mut()
+            .for_each(|rule| rule.perlin_active = true);
+        let mut layer = json_data.worlds[0].levels[0]
+            .layer_instances
+            .as_ref()
+            .unwrap()[0]
+            .clone();
+        let layer_def = &json_data.defs.layers[0];
+        let baked = tile_keys(&layer.auto_layer_tiles);
+
+        let source = IntGridSource::new(&layer, layer_def);
+        let region = IRect::new(0, 0, layer.c_wid as i32 - 1, layer.c_hei as i32 - 1);
+        refresh_auto_layer_tiles(
+            &mut layer,
+            layer_def,
+            &json_data.defs.tilesets[0],
+            &source,
+            &[],
+            Some(region),
+        );
+        assert_eq!(tile_keys(&layer.auto_layer_tiles), baked);
+    }
+
+    #[test]
+    fn checker_ignores_offsets() {
+        let mut rule = sample_json().defs.layers[0].auto_rule_groups[0].rules[2].clone();
+        rule.x_offset = 0;
+        let passing = |rule: &AutoLayerRuleDefinition| {
+            (0..4)
+                .flat_map(|cy| (0..6).map(move |cx| (cx, cy)))
+                .filter(|&(cx, cy)| passes_modulo(rule, cx, cy))
+                .collect::<Vec<_>>()
+        };
+        let without_offset = passing(&rule);
+        rule.x_offset = 1;
+        assert_eq!(passing(&rule), without_offset);
+        assert!(without_offset.contains(&(0, 0)) && without_offset.contains(&(1, 1)));
+    }
+}
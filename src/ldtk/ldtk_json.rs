@@ -1,6 +1,10 @@
 // 一些修改
 // 1. layer_instance_type 字段的类型由字符串改为枚举
 // 2. [x, y] 类型的 Vec 修改为 Vec2
+// 3. 每个结构体增加 unknown_fields 字段, 保存时原样写回未识别的字段 (TileInstance 除外, flatten 会让
+//    serde 缓存每个瓦片对象, 大地图加载明显变慢)
+// 4. 每个枚举增加 Unknown(String), 新版本 LDtk 的取值原样读写
+// 5. 编辑器不再导出的字段为 None 时不写出
 
 // Example code that deserializes and serializes the model.
 // extern crate serde;
@@ -32,6 +36,7 @@ pub struct LdtkJson {
     /// This object is not actually used by LDtk. It ONLY exists to force explicit references to
     /// all types, to make sure QuickType finds them and integrate all of them. Otherwise,
     /// Quicktype will drop types that are not explicitely used.
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "__FORCED_REFS")]
     pub forced_refs: Option<ForcedRefs>,
 
@@ -96,6 +101,7 @@ pub struct LdtkJson {
 
     /// **WARNING**: this deprecated value is no longer exported since version 0.9.3  Replaced
     /// by: `imageExportMode`
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub export_png: Option<bool>,
 
     /// If TRUE, a Tiled compatible file will also be generated along with the LDtk JSON file
@@ -183,6 +189,9 @@ pub struct LdtkJson {
     /// supporting this future update easily, please refer to this documentation:
     /// https://github.com/deepnight/ldtk/issues/231
     pub worlds: Vec<World>,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -191,6 +200,9 @@ pub struct LdtkCustomCommand {
 
     /// Possible values: `Manual`, `AfterLoad`, `BeforeSave`, `AfterSave`
     pub when: When,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Possible values: `Manual`, `AfterLoad`, `BeforeSave`, `AfterSave`
@@ -206,6 +218,10 @@ pub enum When {
     BeforeSave,
 
     Manual,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// If you're writing your own LDtk importer, you should probably just ignore *most* stuff in
@@ -237,6 +253,9 @@ pub struct Definitions {
 
     /// All tilesets
     pub tilesets: Vec<TilesetDefinition>,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -347,6 +366,9 @@ pub struct EntityDefinition {
 
     /// Pixel width
     pub width: i64,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 /// This section is mostly only intended for the LDtk editor app itself. You can safely
@@ -464,6 +486,9 @@ pub struct FieldDefinition {
     /// color in the editor UI. For Enum fields, this would be the color associated to their
     /// values.
     pub use_for_smart_color: bool,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Possible values: `Any`, `OnlySame`, `OnlyTags`, `OnlySpecificEntity`
@@ -479,6 +504,10 @@ pub enum AllowedRefs {
 
     #[serde(rename = "OnlyTags")]
     OnlyTags,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Possible values: `Hidden`, `ValueOnly`, `NameAndValue`, `EntityTile`, `LevelTile`,
@@ -529,6 +558,10 @@ pub enum EditorDisplayMode {
 
     #[serde(rename = "ValueOnly")]
     ValueOnly,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Possible values: `Above`, `Center`, `Beneath`
@@ -539,6 +572,10 @@ pub enum EditorDisplayPos {
     Beneath,
 
     Center,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Possible values: `ZigZag`, `StraightArrow`, `CurvedArrow`, `ArrowsLine`, `DashedLine`
@@ -558,6 +595,10 @@ pub enum EditorLinkStyle {
 
     #[serde(rename = "ZigZag")]
     ZigZag,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -591,6 +632,10 @@ pub enum TextLanguageMode {
 
     #[serde(rename = "LangXml")]
     LangXml,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Possible values: `DiscardOldOnes`, `PreventAdding`, `MoveLastOne`
@@ -604,6 +649,10 @@ pub enum LimitBehavior {
 
     #[serde(rename = "PreventAdding")]
     PreventAdding,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// If TRUE, the maxCount is a "per world" limit, if FALSE, it's a "per level". Possible
@@ -618,6 +667,10 @@ pub enum LimitScope {
 
     #[serde(rename = "PerWorld")]
     PerWorld,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Possible values: `Rectangle`, `Ellipse`, `Tile`, `Cross`
@@ -630,6 +683,10 @@ pub enum RenderMode {
     Rectangle,
 
     Tile,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// This object represents a custom sub rectangle in a Tileset image.
@@ -650,6 +707,9 @@ pub struct TilesetRectangle {
 
     /// Y pixels coordinate of the top-left corner in the Tileset image
     pub y: i64,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 /// An enum describing how the the Entity tile is rendered inside the Entity bounds. Possible
@@ -674,6 +734,10 @@ pub enum TileRenderMode {
     Repeat,
 
     Stretch,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -698,6 +762,9 @@ pub struct EnumDefinition {
 
     /// All possible enum values, with their optional Tile infos.
     pub values: Vec<EnumValueDefinition>,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -720,6 +787,9 @@ pub struct EnumValueDefinition {
 
     /// Optional tileset rectangle to represents this value
     pub tile_rect: Option<TilesetRectangle>,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -838,6 +908,9 @@ pub struct LayerDefinition {
 
     /// Asynchronous rendering option for large/complex layers
     pub use_async_render: bool,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -848,6 +921,7 @@ pub struct AutoLayerRuleGroup {
     pub biome_requirement_mode: i64,
 
     /// *This field was removed in 1.0.0 and should no longer be used.*
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub collapsed: Option<bool>,
 
     pub color: Option<String>,
@@ -865,6 +939,9 @@ pub struct AutoLayerRuleGroup {
     pub uid: i64,
 
     pub uses_wizard: bool,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 /// This complex section isn't meant to be used by game devs at all, as these rules are
@@ -923,6 +1000,7 @@ pub struct AutoLayerRuleDefinition {
 
     /// **WARNING**: this deprecated value is no longer exported since version 1.5.0  Replaced
     /// by: `tileRectsIds`
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub tile_ids: Option<Vec<i64>>,
 
     /// Defines how tileIds array is used Possible values: `Single`, `Stamp`
@@ -963,6 +1041,9 @@ pub struct AutoLayerRuleDefinition {
 
     /// Y cell start offset
     pub y_offset: i64,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Checker mode Possible values: `None`, `Horizontal`, `Vertical`
@@ -973,6 +1054,10 @@ pub enum Checker {
     None,
 
     Vertical,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Defines how tileIds array is used Possible values: `Single`, `Stamp`
@@ -981,6 +1066,10 @@ pub enum TileMode {
     Single,
 
     Stamp,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// IntGrid value definition
@@ -999,6 +1088,9 @@ pub struct IntGridValueDefinition {
 
     /// The IntGrid value itself
     pub value: i64,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 /// IntGrid value group definition
@@ -1012,6 +1104,9 @@ pub struct IntGridValueGroupDefinition {
 
     /// Group unique ID
     pub uid: i64,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Type of the layer as Haxe Enum Possible values: `IntGrid`, `Entities`, `Tiles`,
@@ -1027,6 +1122,10 @@ pub enum Type {
     IntGrid,
 
     Tiles,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// The `Tileset` definition is the most important part among project definitions. It
@@ -1090,6 +1189,9 @@ pub struct TilesetDefinition {
 
     /// Unique Intidentifier
     pub uid: i64,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 /// In a tileset definition, user defined meta-data of a tile.
@@ -1099,12 +1201,19 @@ pub struct TileCustomMetadata {
     pub data: String,
 
     pub tile_id: i64,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum EmbedAtlas {
     #[serde(rename = "LdtkIcons")]
     LdtkIcons,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// In a tileset definition, enum based tag infos
@@ -1114,6 +1223,9 @@ pub struct EnumTagValue {
     pub enum_value_id: String,
 
     pub tile_ids: Vec<i64>,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1138,6 +1250,10 @@ pub enum Flag {
 
     #[serde(rename = "UseMultilinesType")]
     UseMultilinesType,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// This object is not actually used by LDtk. It ONLY exists to force explicit references to
@@ -1201,6 +1317,9 @@ pub struct ForcedRefs {
     pub toc_instance_data: Option<LdtkTocInstanceData>,
 
     pub world: Option<World>,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1260,6 +1379,9 @@ pub struct EntityInstance {
     /// Entity width in pixels. For non-resizable entities, it will be the same as Entity
     /// definition.
     pub width: i64,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1282,13 +1404,15 @@ pub struct FieldInstance {
 
     /// Actual value of the field instance. The value type varies, depending on `__type`:<br/>
     /// - For **classic types** (ie. Integer, Float, Boolean, String, Text and FilePath), you
-    /// just get the actual value with the expected type.<br/>   - For **Color**, the value is an
-    /// hexadecimal string using "#rrggbb" format.<br/>   - For **Enum**, the value is a String
-    /// representing the selected enum value.<br/>   - For **Point**, the value is a
-    /// [GridPoint](#ldtk-GridPoint) object.<br/>   - For **Tile**, the value is a
-    /// [TilesetRect](#ldtk-TilesetRect) object.<br/>   - For **EntityRef**, the value is an
-    /// [EntityReferenceInfos](#ldtk-EntityReferenceInfos) object.<br/><br/>  If the field is an
-    /// array, then this `__value` will also be a JSON array.
+    ///   just get the actual value with the expected type.<br/>
+    /// - For **Color**, the value is an hexadecimal string using "#rrggbb" format.<br/>
+    /// - For **Enum**, the value is a String representing the selected enum value.<br/>
+    /// - For **Point**, the value is a [GridPoint](#ldtk-GridPoint) object.<br/>
+    /// - For **Tile**, the value is a [TilesetRect](#ldtk-TilesetRect) object.<br/>
+    /// - For **EntityRef**, the value is an
+    ///   [EntityReferenceInfos](#ldtk-EntityReferenceInfos) object.<br/><br/>
+    ///
+    /// If the field is an array, then this `__value` will also be a JSON array.
     #[serde(rename = "__value")]
     pub value: Option<serde_json::Value>,
 
@@ -1297,6 +1421,9 @@ pub struct FieldInstance {
 
     /// Editor internal raw values
     pub real_editor_values: Vec<Option<serde_json::Value>>,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 /// This object describes the "location" of an Entity instance in the project worlds.
@@ -1316,6 +1443,9 @@ pub struct ReferenceToAnEntityInstance {
 
     /// IID of the World containing the refered EntityInstance
     pub world_iid: String,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 /// This object is just a grid-based coordinate used in Field values.
@@ -1326,6 +1456,9 @@ pub struct GridPoint {
 
     /// Y grid-based coordinate
     pub cy: i64,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 /// IntGrid value instance
@@ -1337,6 +1470,9 @@ pub struct IntGridValueInstance {
 
     /// IntGrid value
     pub v: i64,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1397,6 +1533,7 @@ pub struct LayerInstance {
 
     /// **WARNING**: this deprecated value is no longer exported since version 1.0.0  Replaced
     /// by: `intGridCsv`
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub int_grid: Option<Vec<IntGridValueInstance>>,
 
     /// A list of all values in the IntGrid layer, stored in CSV format (Comma Separated
@@ -1433,6 +1570,9 @@ pub struct LayerInstance {
 
     /// Layer instance visibility
     pub visible: bool,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 /// This structure represents a single tile from a given Tileset.
@@ -1446,8 +1586,10 @@ pub struct TileInstance {
     pub d: Vec<i64>,
 
     /// "Flip bits", a 2-bits integer to represent the mirror transformations of the tile.<br/>
-    /// - Bit 0 = X flip<br/>   - Bit 1 = Y flip<br/>   Examples: f=0 (no flip), f=1 (X flip
-    /// only), f=2 (Y flip only), f=3 (both flips)
+    /// - Bit 0 = X flip<br/>
+    /// - Bit 1 = Y flip<br/>
+    ///
+    /// Examples: f=0 (no flip), f=1 (X flip only), f=2 (Y flip only), f=3 (both flips)
     pub f: i64,
 
     /// Pixel coordinates of the tile in the **layer** (`[x,y]` format). Don't forget optional
@@ -1559,6 +1701,9 @@ pub struct Level {
     /// positioning is manual (ie. GridVania, Free). For Horizontal and Vertical layouts, the
     /// value is always -1 here.
     pub world_y: i64,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Level background image position info
@@ -1577,6 +1722,9 @@ pub struct LevelBackgroundPosition {
     /// An array containing the `[x,y]` pixel coordinates of the top-left corner of the
     /// **cropped** background image, depending on `bgPos` option.
     pub top_left_px: IVec2,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1591,6 +1739,10 @@ pub enum BgPos {
     Repeat,
 
     Unscaled,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Nearby level info
@@ -1609,7 +1761,11 @@ pub struct NeighbourLevel {
 
     /// **WARNING**: this deprecated value is no longer exported since version 1.2.0  Replaced
     /// by: `levelIid`
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub level_uid: Option<i64>,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1622,6 +1778,9 @@ pub struct LdtkTableOfContentEntry {
     pub instances: Option<Vec<ReferenceToAnEntityInstance>>,
 
     pub instances_data: Vec<LdtkTocInstanceData>,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1641,6 +1800,9 @@ pub struct LdtkTocInstanceData {
     pub world_x: i64,
 
     pub world_y: i64,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 /// **IMPORTANT**: this type is available as a preview. You can rely on it to update your
@@ -1675,6 +1837,9 @@ pub struct World {
     /// An enum that describes how levels are organized in this project (ie. linearly or in a 2D
     /// space). Possible values: `Free`, `GridVania`, `LinearHorizontal`, `LinearVertical`, `null`
     pub world_layout: Option<WorldLayout>,
+
+    #[serde(flatten)]
+    pub unknown_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1689,6 +1854,10 @@ pub enum WorldLayout {
 
     #[serde(rename = "LinearVertical")]
     LinearVertical,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Naming convention for Identifiers (first-letter uppercase, full uppercase etc.) Possible
@@ -1702,6 +1871,10 @@ pub enum IdentifierStyle {
     Lowercase,
 
     Uppercase,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// "Image export" option when saving project. Possible values: `None`, `OneImagePerLayer`,
@@ -1718,4 +1891,8 @@ pub enum ImageExportMode {
 
     #[serde(rename = "OneImagePerLevel")]
     OneImagePerLevel,
+
+    /// Value unknown to this version, written back unchanged when saving
+    #[serde(untagged)]
+    Unknown(String),
 }
@@ -1,9 +1,22 @@
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    hash::{BuildHasher, RandomState},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use bevy::{image::TextureAtlasLayout, math::UVec2};
+use bevy::{
+    image::TextureAtlasLayout,
+    math::{IVec2, UVec2, Vec2},
+};
 use serde::{Deserialize, Serialize};
 
-use crate::ldtk::{ldtk_json::TilesetDefinition, prelude::Level};
+use crate::ldtk::{
+    ldtk_json::{
+        EntityDefinition, EntityInstance, LayerInstance, NeighbourLevel, TileInstance,
+        TilesetDefinition, World, WorldLayout,
+    },
+    prelude::Level,
+};
 
 impl From<&TilesetDefinition> for TextureAtlasLayout {
     fn from(tileset_def: &TilesetDefinition) -> Self {
@@ -25,7 +38,107 @@ impl From<&TilesetDefinition> for TextureAtlasLayout {
     }
 }
 
+impl TilesetDefinition {
+    /// By tile id, from the `opaqueTiles` bitmap of `cached_pixel_data`
+    pub fn opaque_tiles(&self) -> Option<Vec<bool>> {
+        let opaque_tiles = self
+            .cached_pixel_data
+            .as_ref()?
+            .get("opaqueTiles")?
+            .as_ref()?
+            .as_str()?;
+        Some(opaque_tiles.chars().map(|bit| bit == '1').collect())
+    }
+
+    pub fn tile_src(&self, tile_id: i64) -> IVec2 {
+        let columns = self.c_wid.max(1);
+        let cell = IVec2::new((tile_id % columns) as i32, (tile_id / columns) as i32);
+        cell * (self.tile_grid_size + self.spacing) as i32 + IVec2::splat(self.padding as i32)
+    }
+}
+
+impl World {
+    /// Levels of linear layouts, which LDtk leaves at -1, are laid out end to end
+    pub fn level_world_positions(&self) -> impl Iterator<Item = (&Level, IVec2)> {
+        let mut offset = 0;
+        self.levels.iter().map(move |level| {
+            let world_position = match self.world_layout {
+                Some(WorldLayout::LinearHorizontal) => {
+                    offset += level.px_wid;
+                    IVec2::new((offset - level.px_wid) as i32, 0)
+                }
+                Some(WorldLayout::LinearVertical) => {
+                    offset += level.px_hei;
+                    IVec2::new(0, (offset - level.px_hei) as i32)
+                }
+                _ => IVec2::new(level.world_x as i32, level.world_y as i32),
+            };
+            (level, world_position)
+        })
+    }
+
+    pub fn levels_at_depth(&self, world_depth: i64) -> impl Iterator<Item = &Level> {
+        self.levels
+            .iter()
+            .filter(move |level| level.world_depth == world_depth)
+    }
+
+    pub fn neighbour_levels(
+        &self,
+        level: &Level,
+        direction: NeighbourDirection,
+    ) -> impl Iterator<Item = &Level> {
+        level
+            .neighbours
+            .iter()
+            .filter(move |neighbour| neighbour.direction() == Some(direction))
+            .filter_map(|neighbour| {
+                self.levels
+                    .iter()
+                    .find(|level| level.iid == neighbour.level_iid)
+            })
+    }
+
+    pub fn levels_above(&self, level: &Level) -> impl Iterator<Item = &Level> {
+        self.neighbour_levels(level, NeighbourDirection::Above)
+    }
+
+    pub fn levels_below(&self, level: &Level) -> impl Iterator<Item = &Level> {
+        self.neighbour_levels(level, NeighbourDirection::Below)
+    }
+
+    pub fn level_world_position(&self, level_iid: &str) -> Option<IVec2> {
+        self.level_world_positions()
+            .find(|(level, _)| level.iid == level_iid)
+            .map(|(_, world_position)| world_position)
+    }
+}
+
 impl Level {
+    pub fn layer_instance(&self, layer_iid: &str) -> Option<&LayerInstance> {
+        self.layer_instances
+            .iter()
+            .flatten()
+            .find(|layer| layer.iid == layer_iid)
+    }
+
+    pub fn layer_instance_mut(&mut self, layer_iid: &str) -> Option<&mut LayerInstance> {
+        self.layer_instances
+            .iter_mut()
+            .flatten()
+            .find(|layer| layer.iid == layer_iid)
+    }
+
+    pub fn entity_instance(&self, entity_iid: &str) -> Option<(&LayerInstance, &EntityInstance)> {
+        self.layer_instances.iter().flatten().find_map(|layer| {
+            layer
+                .entity_instances
+                .iter()
+                .find(|entity| entity.iid == entity_iid)
+                .map(|entity| (layer, entity))
+        })
+    }
+
     pub fn get_tileset_uid_set(&self) -> HashSet<i64> {
         self.layer_instances
             .iter()
@@ -34,12 +147,199 @@ impl Level {
                     .iter()
                     .map(|layer| layer.override_tileset_uid.or(layer.tileset_def_uid))
             })
-            .filter_map(|uid| uid)
+            .flatten()
             .collect::<HashSet<_>>()
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl LayerInstance {
+    pub fn contains_grid_coord(&self, grid_coord: IVec2) -> bool {
+        grid_coord.x >= 0
+            && grid_coord.y >= 0
+            && (grid_coord.x as i64) < self.c_wid
+            && (grid_coord.y as i64) < self.c_hei
+    }
+
+    /// Also the `coordId` of tile data
+    pub fn coord_id(&self, grid_coord: IVec2) -> i64 {
+        grid_coord.x as i64 + grid_coord.y as i64 * self.c_wid
+    }
+
+    pub fn px_to_translation(&self, px: IVec2) -> Vec2 {
+        Vec2::new(
+            (px.x as i64 + self.px_total_offset_x) as f32,
+            (-px.y as i64 + self.px_total_offset_y) as f32,
+        )
+    }
+
+    pub fn translation_to_px(&self, translation: Vec2) -> Vec2 {
+        Vec2::new(
+            translation.x - self.px_total_offset_x as f32,
+            self.px_total_offset_y as f32 - translation.y,
+        )
+    }
+
+    /// Tile sprites are centered on their `px`, so cells are found by rounding
+    pub fn translation_to_grid_coord(&self, translation: Vec2) -> IVec2 {
+        (self.translation_to_px(translation) / self.grid_size as f32)
+            .round()
+            .as_ivec2()
+    }
+
+    pub fn int_grid_value(&self, grid_coord: IVec2) -> Option<i64> {
+        if !self.contains_grid_coord(grid_coord) {
+            return None;
+        }
+        self.int_grid_csv
+            .get(self.coord_id(grid_coord) as usize)
+            .copied()
+    }
+
+    pub fn set_int_grid_value(&mut self, grid_coord: IVec2, value: i64) -> bool {
+        if !self.contains_grid_coord(grid_coord) {
+            return false;
+        }
+        let coord_id = self.coord_id(grid_coord) as usize;
+        match self.int_grid_csv.get_mut(coord_id) {
+            Some(cell) => {
+                *cell = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_grid_tile(&mut self, grid_coord: IVec2, tile: Option<TileInstance>) {
+        let px = grid_coord * self.grid_size as i32;
+        self.grid_tiles.retain(|grid_tile| grid_tile.px != px);
+        self.grid_tiles.extend(tile);
+    }
+
+    /// The tile goes with the grid tiles of a Tiles layer, with the auto-layer tiles otherwise
+    pub fn set_tile(&mut self, grid_coord: IVec2, tile: Option<TileInstance>) {
+        let px = grid_coord * self.grid_size as i32;
+        self.grid_tiles.retain(|grid_tile| grid_tile.px != px);
+        self.auto_layer_tiles
+            .retain(|auto_layer_tile| auto_layer_tile.px != px);
+
+        match self.layer_instance_type {
+            LayerInstanceType::Tiles => self.grid_tiles.extend(tile),
+            _ => self.auto_layer_tiles.extend(tile),
+        }
+    }
+
+    /// Grid tiles are stacked beneath auto-layer tiles, like when spawned
+    pub fn stacked_tile_mut(
+        &mut self,
+        grid_coord: IVec2,
+        stack_index: usize,
+    ) -> Option<&mut TileInstance> {
+        let px = grid_coord * self.grid_size as i32;
+        self.grid_tiles
+            .iter_mut()
+            .chain(self.auto_layer_tiles.iter_mut())
+            .filter(|tile| tile.px == px)
+            .nth(stack_index)
+    }
+
+    pub fn new_grid_tile(
+        &self,
+        tileset_def: &TilesetDefinition,
+        grid_coord: IVec2,
+        tile_id: i64,
+        flip_bits: i64,
+    ) -> TileInstance {
+        TileInstance {
+            a: 1.,
+            d: vec![self.coord_id(grid_coord)],
+            f: flip_bits,
+            px: grid_coord * self.grid_size as i32,
+            src: tileset_def.tile_src(tile_id),
+            t: tile_id,
+        }
+    }
+}
+
+impl NeighbourLevel {
+    /// `None` for directions unknown to this version
+    pub fn direction(&self) -> Option<NeighbourDirection> {
+        match self.dir.as_str() {
+            "n" => Some(NeighbourDirection::North),
+            "s" => Some(NeighbourDirection::South),
+            "w" => Some(NeighbourDirection::West),
+            "e" => Some(NeighbourDirection::East),
+            "nw" => Some(NeighbourDirection::NorthWest),
+            "ne" => Some(NeighbourDirection::NorthEast),
+            "sw" => Some(NeighbourDirection::SouthWest),
+            "se" => Some(NeighbourDirection::SouthEast),
+            ">" => Some(NeighbourDirection::Above),
+            "<" => Some(NeighbourDirection::Below),
+            "o" => Some(NeighbourDirection::Overlap),
+            _ => None,
+        }
+    }
+}
+
+impl EntityInstance {
+    pub fn new(
+        entity_def: &EntityDefinition,
+        level_world_position: IVec2,
+        layer: &LayerInstance,
+        px: IVec2,
+    ) -> Self {
+        let mut entity_instance = Self {
+            grid: IVec2::ZERO,
+            identifier: entity_def.identifier.clone(),
+            pivot: Vec2::new(entity_def.pivot_x as f32, entity_def.pivot_y as f32),
+            smart_color: entity_def.color.clone(),
+            tags: entity_def.tags.clone(),
+            tile: entity_def.tile_rect.clone(),
+            world_x: None,
+            world_y: None,
+            def_uid: entity_def.uid,
+            field_instances: Vec::new(),
+            height: entity_def.height,
+            iid: new_iid(),
+            px: IVec2::ZERO,
+            width: entity_def.width,
+            unknown_fields: Default::default(),
+        };
+        entity_instance.set_px(level_world_position, layer, px);
+        entity_instance
+    }
+
+    pub fn set_px(&mut self, level_world_position: IVec2, layer: &LayerInstance, px: IVec2) {
+        self.px = px;
+        self.grid = px.div_euclid(IVec2::splat(layer.grid_size.max(1) as i32));
+        let world_px = level_world_position + px;
+        self.world_x = Some(world_px.x as i64);
+        self.world_y = Some(world_px.y as i64);
+    }
+}
+
+/// Formatted like the UUIDs LDtk uses
+pub fn new_iid() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+    let high = RandomState::new().hash_one(nanos);
+    let low = RandomState::new().hash_one(high);
+    let bits = ((high as u128) << 64 | low as u128) & !(0xf000 << 64) & !(0xc << 60)
+        | 0x4000 << 64
+        | 0x8 << 60;
+
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        bits >> 96,
+        (bits >> 80) & 0xffff,
+        (bits >> 64) & 0xffff,
+        (bits >> 48) & 0xffff,
+        bits & 0xffff_ffff_ffff
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum LayerInstanceType {
     #[serde(rename = "IntGrid")]
     IntGrid,
@@ -53,3 +353,82 @@ pub enum LayerInstanceType {
     #[serde(rename = "AutoLayer")]
     AutoLayer,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NeighbourDirection {
+    North,
+    South,
+    West,
+    East,
+    NorthWest,
+    NorthEast,
+    SouthWest,
+    SouthEast,
+    /// The neighbour has a greater `world_depth` and overlaps the level
+    Above,
+    /// The neighbour has a lower `world_depth` and overlaps the level
+    Below,
+    /// The neighbour overlaps the level at the same `world_depth`
+    Overlap,
+}
+
+impl NeighbourDirection {
+    pub fn is_depth(self) -> bool {
+        matches!(self, Self::Above | Self::Below)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ldtk::test_fixtures::sample_json;
+
+    #[test]
+    fn free_levels_keep_negative_world_positions() {
+        let mut world = sample_json().worlds[0].clone();
+        world.world_layout = Some(WorldLayout::Free);
+        world.levels[1].world_x = -1;
+        world.levels[1].world_y = -1;
+
+        let level_iid = world.levels[1].iid.clone();
+        assert_eq!(
+            world.level_world_position(&level_iid),
+            Some(IVec2::splat(-1))
+        );
+    }
+
+    #[test]
+    fn entity_world_coordinates_follow_linear_layouts() {
+        let mut world = sample_json().worlds[0].clone();
+        world.levels.iter_mut().for_each(|level| {
+            level.world_x = -1;
+            level.world_y = -1;
+        });
+        let level = &world.levels[1];
+        let layer = level.layer_instances.iter().flatten().next().unwrap();
+        let level_world_position = world.level_world_position(&level.iid).unwrap();
+        let mut entity_instance: EntityInstance = serde_json::from_value(serde_json::json!({
+            "__grid": [0, 0],
+            "__identifier": "Player",
+            "__pivot": [0, 0],
+            "__smartColor": "#FFFFFF",
+            "__tags": [],
+            "__tile": null,
+            "__worldX": null,
+            "__worldY": null,
+            "defUid": 1,
+            "fieldInstances": [],
+            "height": 8,
+            "iid": new_iid(),
+            "px": [0, 0],
+            "width": 8,
+        }))
+        .unwrap();
+
+        entity_instance.set_px(level_world_position, layer, IVec2::new(4, 8));
+        assert_eq!(
+            (entity_instance.world_x, entity_instance.world_y),
+            (Some(level.px_wid + 4), Some(8))
+        );
+    }
+}
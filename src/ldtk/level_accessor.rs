@@ -1,3 +1,5 @@
+use bevy::math::{IRect, IVec2};
+
 use crate::{
     ldtk::ldtk_json::{LdtkJson, Level, World},
     resources::prelude::LevelSelection,
@@ -9,23 +11,6 @@ type IterWorldLevels<'a> = std::iter::FlatMap<
     fn(&World) -> std::slice::Iter<'_, Level>,
 >;
 
-type ZipWorldIndexAndLevel<'a> =
-    std::iter::Zip<std::iter::Repeat<usize>, std::iter::Enumerate<std::slice::Iter<'a, Level>>>;
-
-type IterWorldLevelsWithIndices<'a> = std::iter::FlatMap<
-    std::iter::Enumerate<std::slice::Iter<'a, World>>,
-    std::iter::Map<
-        ZipWorldIndexAndLevel<'a>,
-        fn((usize, (usize, &Level))) -> (usize, usize, &Level),
-    >,
-    fn(
-        (usize, &World),
-    ) -> std::iter::Map<
-        ZipWorldIndexAndLevel<'_>,
-        fn((usize, (usize, &Level))) -> (usize, usize, &Level),
-    >,
->;
-
 pub(crate) trait LevelAccessor {
     fn worlds(&self) -> &[World];
 
@@ -33,22 +18,72 @@ pub(crate) trait LevelAccessor {
         self.worlds().iter().flat_map(|world| world.levels.iter())
     }
 
-    fn iter_world_levels_with_indices(&self) -> IterWorldLevelsWithIndices<'_> {
+    fn find_world_level_by_iid(&self, level_iid: &str) -> Option<(&World, &Level)> {
         self.worlds()
             .iter()
-            .enumerate()
-            .flat_map(|(world_index, world)| {
-                std::iter::repeat(world_index)
-                    .zip(world.levels.iter().enumerate())
-                    .map(|(world_index, (level_index, level))| (world_index, level_index, level))
-            })
+            .flat_map(|world| std::iter::repeat(world).zip(world.levels.iter()))
+            .find(|(_, level)| level_iid == level.iid)
     }
 
-    fn find_world_level_by_iid(&self, level_iid: &str) -> Option<(&World, &Level)> {
+    fn find_world_level(
+        &self,
+        predicate: impl Fn(&World, &Level) -> bool,
+    ) -> Option<(&World, &Level)> {
         self.worlds()
             .iter()
             .flat_map(|world| std::iter::repeat(world).zip(world.levels.iter()))
-            .find(|(_, level)| level_iid == &level.iid)
+            .find(|(world, level)| predicate(world, level))
+    }
+
+    fn find_world_level_by_identifier(
+        &self,
+        world_identifier: Option<&str>,
+        level_identifier: &str,
+    ) -> Option<(&World, &Level)> {
+        self.find_world_level(|world, level| {
+            world_identifier.is_none_or(|world_identifier| world.identifier == world_identifier)
+                && level.identifier == level_identifier
+        })
+    }
+
+    fn find_world_level_by_uid(&self, level_uid: i64) -> Option<(&World, &Level)> {
+        self.find_world_level(|_, level| level.uid == level_uid)
+    }
+
+    /// `position` is in pixels relative to the world
+    fn find_world_level_at_position(
+        &self,
+        world_identifier: Option<&str>,
+        position: IVec2,
+    ) -> Option<(&World, &Level)> {
+        self.worlds()
+            .iter()
+            .filter(|world| {
+                world_identifier.is_none_or(|world_identifier| world.identifier == world_identifier)
+            })
+            .find_map(|world| {
+                world
+                    .level_world_positions()
+                    .find(|(level, world_position)| {
+                        let px_size = IVec2::new(level.px_wid as i32, level.px_hei as i32);
+                        IRect::from_corners(*world_position, world_position + px_size - 1)
+                            .contains(position)
+                    })
+                    .map(|(level, _)| (world, level))
+            })
+    }
+
+    fn find_world_level_by_field_value(
+        &self,
+        field_identifier: &str,
+        value: &serde_json::Value,
+    ) -> Option<(&World, &Level)> {
+        self.find_world_level(|_, level| {
+            level.field_instances.iter().any(|field_instance| {
+                field_instance.identifier == field_identifier
+                    && field_instance.value.as_ref() == Some(value)
+            })
+        })
     }
 
     fn find_world_level_at_indices(
@@ -56,20 +91,56 @@ pub(crate) trait LevelAccessor {
         world_index: usize,
         level_index: usize,
     ) -> Option<(&World, &Level)> {
-        self.worlds().get(world_index).and_then(|world| {
-            world
-                .levels
-                .get(level_index)
-                .and_then(|level| Some((world, level)))
-        })
+        self.worlds()
+            .get(world_index)
+            .and_then(|world| Some((world, world.levels.get(level_index)?)))
     }
 
-    fn find_world_level(&self, level_selection: &LevelSelection) -> Option<(&World, &Level)> {
+    /// Levels of other worlds than the first selected one are left out
+    fn find_world_levels(
+        &self,
+        level_selection: &LevelSelection,
+        current_level_iid: Option<&str>,
+    ) -> Option<(&World, Vec<&Level>)> {
         match level_selection {
-            LevelSelection::ByIndices(world_index, level_index) => {
-                self.find_world_level_at_indices(*world_index, *level_index)
+            LevelSelection::ByIndices(world_index, level_index) => self
+                .find_world_level_at_indices(*world_index, *level_index)
+                .map(|(world, level)| (world, vec![level])),
+            LevelSelection::ByIID(iid) => self
+                .find_world_level_by_iid(iid)
+                .map(|(world, level)| (world, vec![level])),
+            LevelSelection::ByIIDs(iids) => {
+                let (world, _) = iids
+                    .iter()
+                    .find_map(|iid| self.find_world_level_by_iid(iid))?;
+                let levels = world
+                    .levels
+                    .iter()
+                    .filter(|level| iids.contains(&level.iid))
+                    .collect();
+                Some((world, levels))
+            }
+            LevelSelection::AllOfWorld(world_index) => {
+                let world = self.worlds().get(*world_index)?;
+                Some((world, world.levels.iter().collect()))
+            }
+            LevelSelection::ByIdentifier { world, level } => self
+                .find_world_level_by_identifier(world.as_deref(), level)
+                .map(|(world, level)| (world, vec![level])),
+            LevelSelection::ByUid(uid) => self
+                .find_world_level_by_uid(*uid)
+                .map(|(world, level)| (world, vec![level])),
+            LevelSelection::ByWorldPosition { world, position } => self
+                .find_world_level_at_position(world.as_deref(), *position)
+                .map(|(world, level)| (world, vec![level])),
+            LevelSelection::ByNeighbour(direction) => {
+                let (world, current_level) = self.find_world_level_by_iid(current_level_iid?)?;
+                let level = world.neighbour_levels(current_level, *direction).next()?;
+                Some((world, vec![level]))
             }
-            LevelSelection::ByIID(iid) => self.find_world_level_by_iid(iid),
+            LevelSelection::ByFieldValue { identifier, value } => self
+                .find_world_level_by_field_value(identifier, value)
+                .map(|(world, level)| (world, vec![level])),
         }
     }
 }
@@ -0,0 +1,262 @@
+use bevy::math::{IRect, IVec2, UVec2};
+
+use crate::ldtk::{
+    auto_layer::refresh_dependent_auto_layers,
+    ldtk_json::{Definitions, LayerDefinition, LayerInstance, Level, Type},
+    ldtk_json_ext::{LayerInstanceType, new_iid},
+    level_builder_error::LevelBuilderError,
+};
+
+/// Builds a level at runtime from IntGrid cells, register it with
+/// [`LDtkProject::add_level`](crate::prelude::LDtkProject::add_level)
+pub struct LevelBuilder {
+    identifier: String,
+    layer_def_uid: i64,
+    grid_size: i64,
+    size: UVec2,
+    int_grid_csv: Vec<i64>,
+    world_iid: Option<String>,
+    world_position: IVec2,
+    world_depth: i64,
+    seed: Option<i64>,
+}
+
+impl LevelBuilder {
+    /// `int_grid_csv` is read row by row, missing cells are left empty
+    pub fn new(
+        identifier: impl Into<String>,
+        layer_def: &LayerDefinition,
+        size: UVec2,
+        int_grid_csv: impl IntoIterator<Item = i64>,
+    ) -> Result<Self, LevelBuilderError> {
+        if !matches!(layer_def.purple_type, Type::IntGrid) {
+            return Err(LevelBuilderError::NotIntGridLayer(
+                layer_def.identifier.clone(),
+            ));
+        }
+
+        let mut int_grid_csv = int_grid_csv.into_iter().collect::<Vec<_>>();
+        int_grid_csv.resize(size.element_product() as usize, 0);
+
+        Ok(Self {
+            identifier: identifier.into(),
+            layer_def_uid: layer_def.uid,
+            grid_size: layer_def.grid_size.max(1),
+            size,
+            int_grid_csv,
+            world_iid: None,
+            world_position: IVec2::ZERO,
+            world_depth: 0,
+            seed: None,
+        })
+    }
+
+    /// The first world of the project by default
+    pub fn world(mut self, world_iid: impl Into<String>) -> Self {
+        self.world_iid = Some(world_iid.into());
+        self
+    }
+
+    pub fn world_position(mut self, world_position: IVec2) -> Self {
+        self.world_position = world_position;
+        self
+    }
+
+    pub fn world_depth(mut self, world_depth: i64) -> Self {
+        self.world_depth = world_depth;
+        self
+    }
+
+    /// Derived from the level uid by default
+    pub fn seed(mut self, seed: i64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub(crate) fn world_iid(&self) -> Option<&str> {
+        self.world_iid.as_deref()
+    }
+
+    /// One layer instance per layer definition of a known type, in the project order
+    pub(crate) fn build(self, defs: &Definitions, level_uid: i64, bg_color: &str) -> Level {
+        let px_size = self.size.as_ivec2() * self.grid_size as i32;
+        let seed = self.seed.unwrap_or(level_uid);
+
+        let mut level = Level {
+            bg_color: bg_color.to_string(),
+            bg_pos: None,
+            neighbours: Vec::new(),
+            smart_color: bg_color.to_string(),
+            level_bg_color: None,
+            bg_pivot_x: 0.5,
+            bg_pivot_y: 0.5,
+            level_bg_pos: None,
+            bg_rel_path: None,
+            external_rel_path: None,
+            field_instances: Vec::new(),
+            identifier: self.identifier,
+            iid: new_iid(),
+            layer_instances: Some(Vec::new()),
+            px_hei: px_size.y as i64,
+            px_wid: px_size.x as i64,
+            uid: level_uid,
+            use_auto_identifier: false,
+            world_depth: self.world_depth,
+            world_x: self.world_position.x as i64,
+            world_y: self.world_position.y as i64,
+            unknown_fields: Default::default(),
+        };
+
+        let layer_instances = defs
+            .layers
+            .iter()
+            .filter_map(|layer_def| {
+                let mut layer = new_layer_instance(defs, layer_def, &level, seed)?;
+                if layer_def.uid == self.layer_def_uid {
+                    layer.int_grid_csv = self.int_grid_csv.clone();
+                }
+                Some(layer)
+            })
+            .collect::<Vec<_>>();
+
+        let source_layer_iid = layer_instances
+            .iter()
+            .find(|layer| layer.layer_def_uid == self.layer_def_uid)
+            .map(|layer| layer.iid.clone());
+        level.layer_instances = Some(layer_instances);
+
+        if let Some(source_layer_iid) = source_layer_iid {
+            let region = IRect::from_corners(IVec2::ZERO, self.size.as_ivec2() - 1);
+            refresh_dependent_auto_layers(&mut level, defs, &source_layer_iid, region);
+        }
+
+        level
+    }
+}
+
+fn new_layer_instance(
+    defs: &Definitions,
+    layer_def: &LayerDefinition,
+    level: &Level,
+    seed: i64,
+) -> Option<LayerInstance> {
+    let grid_size = layer_def.grid_size.max(1);
+    let c_wid = (level.px_wid + grid_size - 1) / grid_size;
+    let c_hei = (level.px_hei + grid_size - 1) / grid_size;
+    let tileset_def_uid = layer_def.tileset_def_uid.or(layer_def.auto_tileset_def_uid);
+    let (layer_instance_type, int_grid_csv) = match layer_def.purple_type {
+        Type::IntGrid => (
+            LayerInstanceType::IntGrid,
+            vec![0; (c_wid * c_hei) as usize],
+        ),
+        Type::Entities => (LayerInstanceType::Entities, Vec::new()),
+        Type::Tiles => (LayerInstanceType::Tiles, Vec::new()),
+        Type::AutoLayer => (LayerInstanceType::AutoLayer, Vec::new()),
+        Type::Unknown(_) => return None,
+    };
+
+    Some(LayerInstance {
+        c_hei,
+        c_wid,
+        grid_size,
+        identifier: layer_def.identifier.clone(),
+        opacity: layer_def.display_opacity,
+        px_total_offset_x: layer_def.px_offset_x,
+        px_total_offset_y: layer_def.px_offset_y,
+        tileset_def_uid,
+        tileset_rel_path: defs
+            .tilesets
+            .iter()
+            .find(|tileset_def| Some(tileset_def.uid) == tileset_def_uid)
+            .and_then(|tileset_def| tileset_def.rel_path.clone()),
+        layer_instance_type,
+        auto_layer_tiles: Vec::new(),
+        entity_instances: Vec::new(),
+        grid_tiles: Vec::new(),
+        iid: new_iid(),
+        int_grid: None,
+        int_grid_csv,
+        layer_def_uid: layer_def.uid,
+        level_id: level.uid,
+        optional_rules: Vec::new(),
+        override_tileset_uid: None,
+        px_offset_x: 0,
+        px_offset_y: 0,
+        seed: seed.wrapping_add(layer_def.uid),
+        visible: true,
+        unknown_fields: Default::default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ldtk::ldtk_json::LdtkJson;
+    use crate::ldtk::test_fixtures::sample_project;
+
+    fn tile_keys(level: &Level) -> Vec<(i64, i64, [i32; 2], i64)> {
+        let mut keys = level.layer_instances.as_ref().unwrap()[0]
+            .auto_layer_tiles
+            .iter()
+            .map(|tile| (tile.d[0], tile.d[1], tile.px.into(), tile.f))
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn built_level_is_auto_tiled_and_saved() {
+        let mut project = sample_project();
+        let sample_level = project.json_data().worlds[0].levels[0].clone();
+        let layer_def = project.json_data().defs.layers[0].clone();
+        let int_grid_csv = sample_level.layer_instances.as_ref().unwrap()[0]
+            .int_grid_csv
+            .clone();
+
+        let builder = LevelBuilder::new("Generated", &layer_def, UVec2::new(6, 4), int_grid_csv)
+            .unwrap()
+            .world_position(IVec2::new(0, 64));
+        let level = project.add_level(builder).unwrap();
+        assert_eq!(tile_keys(level), tile_keys(&sample_level));
+        let level_iid = level.iid.clone();
+
+        let project_path = std::env::temp_dir().join(format!("bevy_ldtk_{level_iid}.ldtk"));
+        project.save(&project_path).unwrap();
+        let saved: LdtkJson =
+            serde_json::from_slice(&std::fs::read(&project_path).unwrap()).unwrap();
+        std::fs::remove_file(&project_path).unwrap();
+
+        let saved_level = saved.worlds[0]
+            .levels
+            .iter()
+            .find(|level| level.iid == level_iid)
+            .unwrap();
+        assert_eq!(saved_level.identifier, "Generated");
+        assert_eq!(tile_keys(saved_level), tile_keys(&sample_level));
+    }
+
+    #[test]
+    fn builder_rejects_layers_without_int_grid() {
+        let mut layer_def = sample_project().json_data().defs.layers[0].clone();
+        layer_def.purple_type = Type::Tiles;
+        let builder = LevelBuilder::new("Generated", &layer_def, UVec2::ONE, []);
+        assert!(matches!(
+            builder,
+            Err(LevelBuilderError::NotIntGridLayer(_))
+        ));
+    }
+
+    #[test]
+    fn adding_level_needs_a_world() {
+        let mut project = sample_project();
+        let layer_def = project.json_data().defs.layers[0].clone();
+        let builder = || LevelBuilder::new("Generated", &layer_def, UVec2::ONE, []).unwrap();
+
+        let result = project.add_level(builder().world("missing"));
+        assert!(matches!(result, Err(LevelBuilderError::WorldNotFound(_))));
+
+        project.json_data_mut().worlds.clear();
+        let result = project.add_level(builder());
+        assert!(matches!(result, Err(LevelBuilderError::NoWorld)));
+    }
+}
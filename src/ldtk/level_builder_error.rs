@@ -0,0 +1,13 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LevelBuilderError {
+    #[error("layer {0} is not an IntGrid layer")]
+    NotIntGridLayer(String),
+
+    #[error("world {0} not found in the ldtk project")]
+    WorldNotFound(String),
+
+    #[error("the ldtk project has no world to add the level to")]
+    NoWorld,
+}
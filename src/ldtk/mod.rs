@@ -1,11 +1,23 @@
+mod auto_layer;
 mod ldtk_json;
 mod ldtk_json_ext;
 mod level_accessor;
+mod level_builder;
+mod level_builder_error;
+mod perlin;
+#[cfg(test)]
+pub(crate) mod test_fixtures;
 
 pub(crate) mod prelude {
+    use crate::ldtk::auto_layer;
     use crate::ldtk::ldtk_json;
     use crate::ldtk::level_accessor;
+    use crate::ldtk::level_builder;
+    use crate::ldtk::level_builder_error;
 
+    pub(crate) use auto_layer::refresh_dependent_auto_layers;
     pub use ldtk_json::*;
     pub(crate) use level_accessor::LevelAccessor;
+    pub use level_builder::LevelBuilder;
+    pub use level_builder_error::LevelBuilderError;
 }
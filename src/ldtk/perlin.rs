@@ -0,0 +1,55 @@
+use std::f64::consts::TAU;
+
+use bevy::math::DVec2;
+
+use crate::ldtk::auto_layer::rand_seed_coords;
+
+/// Resolution of the gradient angles picked for each lattice point
+const GRADIENT_STEPS: i64 = 1 << 16;
+
+fn gradient(seed: i64, x: i32, y: i32) -> DVec2 {
+    let step = rand_seed_coords(seed, x, y, GRADIENT_STEPS);
+    DVec2::from_angle(step as f64 / GRADIENT_STEPS as f64 * TAU)
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn gradient_noise(seed: i64, position: DVec2) -> f64 {
+    let cell = position.floor();
+    let local = position - cell;
+    let (x, y) = (cell.x as i32, cell.y as i32);
+
+    let corner = |dx: i32, dy: i32| {
+        gradient(seed, x + dx, y + dy).dot(local - DVec2::new(dx as f64, dy as f64))
+    };
+
+    let u = fade(local.x);
+    let v = fade(local.y);
+    let top = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * u;
+    let bottom = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * u;
+    top + (bottom - top) * v
+}
+
+/// Fractal gradient noise sampled by auto-layer rules with `perlin_active`, normalized by the
+/// sum of the octave amplitudes. Only the cells where it is positive or zero pass the rule.
+///
+/// This is not the `hxd.Perlin` noise of the editor, so it doesn't pick the same cells. When
+/// refreshing, cells the editor already placed a perlin rule tile on keep passing that rule,
+/// and the noise only decides for the others.
+pub(crate) fn perlin(seed: i64, x: f64, y: f64, octaves: i64) -> f64 {
+    let mut total = 0.;
+    let mut total_amplitude = 0.;
+    let mut amplitude = 1.;
+    let mut frequency = 1.;
+
+    for octave in 0..octaves.max(1) {
+        total += gradient_noise(seed + octave, DVec2::new(x, y) * frequency) * amplitude;
+        total_amplitude += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.;
+    }
+
+    total / total_amplitude
+}
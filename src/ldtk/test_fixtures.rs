@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+use bevy::{asset::AssetPath, prelude::*};
+
+use crate::asset::prelude::LDtkProject;
+use crate::ldtk::prelude::{LayerInstance, LdtkJson};
+use crate::resources::prelude::{LDtkRenderBackend, LDtkSettings, LDtkTileCulling};
+
+/// Hand-made LDtk 1.5 project, with fields and enum values this crate doesn't know about
+pub(crate) const SAMPLE_PROJECT: &str = include_str!("../../tests/fixtures/auto_layer_rules.ldtk");
+
+pub(crate) fn sample_json() -> LdtkJson {
+    serde_json::from_str(SAMPLE_PROJECT).unwrap()
+}
+
+pub(crate) fn sample_project() -> LDtkProject {
+    LDtkProject::new(&AssetPath::from("auto_layer_rules.ldtk"), sample_json())
+}
+
+pub(crate) fn sample_layer() -> LayerInstance {
+    sample_json().worlds[0].levels[0]
+        .layer_instances
+        .as_ref()
+        .unwrap()[0]
+        .clone()
+}
+
+/// Holds the resources read by `LDtkTileRenderer` and `LDtkTileCommands`
+pub(crate) fn tile_world() -> World {
+    let mut world = World::new();
+    world.init_resource::<LDtkSettings>();
+    world.init_resource::<LDtkRenderBackend>();
+    world.init_resource::<LDtkTileCulling>();
+    world.init_resource::<Assets<Mesh>>();
+    world.init_resource::<Assets<ColorMaterial>>();
+    world.init_resource::<Assets<TextureAtlasLayout>>();
+    world.init_resource::<Assets<LDtkProject>>();
+    world
+}
+
+pub(crate) fn sample_tileset_handles()
+-> HashMap<i64, (usize, Handle<Image>, Handle<TextureAtlasLayout>)> {
+    HashMap::from([(1, (8, Handle::default(), Handle::default()))])
+}
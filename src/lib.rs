@@ -1,30 +1,170 @@
 mod asset;
 mod components;
+mod events;
 mod ldtk;
+mod messages;
 mod resources;
 mod systems;
 
 pub mod prelude {
-    use bevy::prelude::*;
+    use bevy::{
+        ecs::schedule::{InternedScheduleLabel, ScheduleLabel},
+        prelude::*,
+    };
 
     use crate::asset;
     use crate::components;
+    use crate::events;
+    use crate::ldtk;
+    use crate::messages;
     use crate::resources;
     use crate::systems;
 
     pub use asset::prelude::*;
     pub use components::prelude::*;
+    pub use events::prelude::*;
+    pub use ldtk::prelude::{
+        LayerInstanceType, LevelBuilder, LevelBuilderError, NeighbourDirection,
+    };
+    pub use messages::prelude::*;
     pub use resources::prelude::*;
     pub use systems::prelude::*;
 
-    pub(crate) use systems::prelude::{apply_level_selection, spawn_ldtk_world};
+    pub(crate) use systems::prelude::{
+        apply_ldtk_depth_display, apply_ldtk_edits, apply_ldtk_parallax, apply_level_selection,
+        change_ldtk_level_depth, handle_ldtk_editor_input, load_ldtk_levels,
+        refresh_ldtk_auto_layers, spawn_ldtk_world, stream_ldtk_levels, transition_ldtk_levels,
+        update_current_level,
+    };
 
-    pub fn LDtkPlugin(app: &mut App) {
-        app.add_plugins(asset::prelude::plugin);
+    /// Runs its systems in the [`LDtkSystems`] sets of `schedule`, `PostUpdate` by default
+    pub struct LDtkPlugin {
+        pub schedule: InternedScheduleLabel,
+        pub settings: LDtkSettings,
+        pub render_backend: LDtkRenderBackend,
+    }
+
+    impl LDtkPlugin {
+        pub fn in_schedule(schedule: impl ScheduleLabel) -> Self {
+            Self {
+                schedule: schedule.intern(),
+                ..default()
+            }
+        }
+    }
+
+    impl Default for LDtkPlugin {
+        fn default() -> Self {
+            Self {
+                schedule: PostUpdate.intern(),
+                settings: LDtkSettings::default(),
+                render_backend: LDtkRenderBackend::default(),
+            }
+        }
+    }
+
+    impl Plugin for LDtkPlugin {
+        fn build(&self, app: &mut App) {
+            app.add_plugins(asset::prelude::plugin)
+                .insert_resource(self.settings.clone())
+                .insert_resource(self.render_backend)
+                .init_resource::<LDtkTileCulling>()
+                .init_resource::<LDtkDepthDisplay>()
+                .add_message::<LDtkIntGridChanged>();
+
+            app.configure_sets(
+                self.schedule,
+                (
+                    LDtkSystems::SelectLevel,
+                    LDtkSystems::Spawn,
+                    LDtkSystems::PostSpawn,
+                )
+                    .chain()
+                    .before(TransformSystems::Propagate),
+            )
+            .add_systems(
+                self.schedule,
+                (
+                    (apply_level_selection, stream_ldtk_levels, load_ldtk_levels)
+                        .chain()
+                        .in_set(LDtkSystems::SelectLevel),
+                    (spawn_ldtk_world, refresh_ldtk_auto_layers)
+                        .chain()
+                        .in_set(LDtkSystems::Spawn),
+                    (
+                        update_current_level,
+                        apply_ldtk_depth_display,
+                        apply_ldtk_parallax,
+                    )
+                        .in_set(LDtkSystems::PostSpawn),
+                ),
+            );
+        }
+    }
+
+    /// Opt-in in-game editor, its systems run in [`LDtkSystems::SelectLevel`] of `schedule`
+    pub struct LDtkEditorPlugin {
+        pub schedule: InternedScheduleLabel,
+    }
+
+    impl LDtkEditorPlugin {
+        pub fn in_schedule(schedule: impl ScheduleLabel) -> Self {
+            Self {
+                schedule: schedule.intern(),
+            }
+        }
+    }
+
+    impl Default for LDtkEditorPlugin {
+        fn default() -> Self {
+            Self::in_schedule(PostUpdate)
+        }
+    }
+
+    impl Plugin for LDtkEditorPlugin {
+        fn build(&self, app: &mut App) {
+            app.init_resource::<LDtkEditor>()
+                .add_message::<LDtkEdit>()
+                .add_systems(
+                    self.schedule,
+                    (handle_ldtk_editor_input, apply_ldtk_edits)
+                        .chain()
+                        .before(apply_level_selection)
+                        .in_set(LDtkSystems::SelectLevel),
+                );
+        }
+    }
+
+    /// Keeps the level of the [`LDtkPlayer`] and its neighbours spawned, its systems run in
+    /// [`LDtkSystems::SelectLevel`] of `schedule`
+    pub struct LDtkLevelTransitionPlugin {
+        pub schedule: InternedScheduleLabel,
+    }
+
+    impl LDtkLevelTransitionPlugin {
+        pub fn in_schedule(schedule: impl ScheduleLabel) -> Self {
+            Self {
+                schedule: schedule.intern(),
+            }
+        }
+    }
+
+    impl Default for LDtkLevelTransitionPlugin {
+        fn default() -> Self {
+            Self::in_schedule(PostUpdate)
+        }
+    }
 
-        app.add_systems(
-            PostUpdate,
-            (apply_level_selection, spawn_ldtk_world).chain(),
-        );
+    impl Plugin for LDtkLevelTransitionPlugin {
+        fn build(&self, app: &mut App) {
+            app.add_message::<LevelTransition>()
+                .add_message::<ChangeLevelDepth>()
+                .add_systems(
+                    self.schedule,
+                    (transition_ldtk_levels, change_ldtk_level_depth)
+                        .before(apply_level_selection)
+                        .in_set(LDtkSystems::SelectLevel),
+                );
+        }
     }
 }
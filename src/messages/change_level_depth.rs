@@ -0,0 +1,8 @@
+use bevy::ecs::message::Message;
+
+/// The target level must overlap the `LDtkPlayer`
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeLevelDepth {
+    Up,
+    Down,
+}
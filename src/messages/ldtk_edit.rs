@@ -0,0 +1,35 @@
+use bevy::{ecs::message::Message, math::IVec2};
+
+/// Applied to the spawned hierarchy, the `LDtkWorld` and the `LDtkProject` asset
+#[derive(Message, Debug, Clone)]
+pub enum LDtkEdit {
+    PaintTile {
+        layer_iid: String,
+        grid_coord: IVec2,
+        tile_id: i64,
+        flip_bits: i64,
+    },
+    EraseTile {
+        layer_iid: String,
+        grid_coord: IVec2,
+    },
+    /// `0` empties the cell
+    SetIntGridValue {
+        layer_iid: String,
+        grid_coord: IVec2,
+        value: i64,
+    },
+    /// `identifier` names the entity definition
+    AddEntity {
+        layer_iid: String,
+        identifier: String,
+        px: IVec2,
+    },
+    MoveEntity {
+        entity_iid: String,
+        px: IVec2,
+    },
+    DeleteEntity {
+        entity_iid: String,
+    },
+}
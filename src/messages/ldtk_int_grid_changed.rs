@@ -0,0 +1,20 @@
+use bevy::{
+    ecs::message::Message,
+    math::{IRect, IVec2},
+};
+
+/// Auto-layers reading the layer are refreshed around `region`, inclusive cell bounds
+#[derive(Message, Debug, Clone)]
+pub struct LDtkIntGridChanged {
+    pub layer_iid: String,
+    pub region: IRect,
+}
+
+impl LDtkIntGridChanged {
+    pub fn cell(layer_iid: impl Into<String>, grid_coord: IVec2) -> Self {
+        Self {
+            layer_iid: layer_iid.into(),
+            region: IRect::from_corners(grid_coord, grid_coord),
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use bevy::ecs::message::Message;
+
+use crate::ldtk::prelude::NeighbourDirection;
+
+/// The `LDtkPlayer` left the level `from` and entered the level `to`
+#[derive(Message, Debug, Clone)]
+pub struct LevelTransition {
+    pub from: String,
+    pub to: String,
+    /// Direction of `to` in the neighbours of `from`, `None` when LDtk doesn't list it
+    pub direction: Option<NeighbourDirection>,
+}
@@ -0,0 +1,13 @@
+mod change_level_depth;
+mod ldtk_edit;
+mod ldtk_int_grid_changed;
+mod level_transition;
+
+pub mod prelude {
+    use crate::messages::{change_level_depth, ldtk_edit, ldtk_int_grid_changed, level_transition};
+
+    pub use change_level_depth::ChangeLevelDepth;
+    pub use ldtk_edit::LDtkEdit;
+    pub use ldtk_int_grid_changed::LDtkIntGridChanged;
+    pub use level_transition::LevelTransition;
+}
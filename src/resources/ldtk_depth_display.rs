@@ -0,0 +1,17 @@
+use bevy::ecs::resource::Resource;
+
+#[derive(Resource, Debug, Clone, Default)]
+pub struct LDtkDepthDisplay {
+    pub mode: LDtkDepthDisplayMode,
+    /// Updated by level transitions
+    pub active_depth: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LDtkDepthDisplayMode {
+    #[default]
+    All,
+    ActiveOnly,
+    /// Opacity multiplier of the levels at other depths
+    Fade(f32),
+}
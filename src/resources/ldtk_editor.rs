@@ -0,0 +1,23 @@
+use bevy::ecs::resource::Resource;
+
+/// Only used with `LDtkEditorPlugin`
+#[derive(Resource, Default)]
+pub struct LDtkEditor {
+    pub enabled: bool,
+    pub layer_iid: Option<String>,
+    pub tool: LDtkEditorTool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub enum LDtkEditorTool {
+    PaintTile {
+        tile_id: i64,
+        flip_bits: i64,
+    },
+    EraseTile,
+    IntGrid(i64),
+    AddEntity(String),
+    #[default]
+    MoveEntity,
+    DeleteEntity,
+}
@@ -0,0 +1,20 @@
+use bevy::{ecs::resource::Resource, math::Vec2};
+
+/// Enables level streaming while present, sizes are in LDtk pixels
+#[derive(Resource, Debug, Clone)]
+pub struct LDtkLevelStreaming {
+    pub half_size: Vec2,
+    pub unload_margin: f32,
+    /// External level files loading at once
+    pub max_pending_loads: usize,
+}
+
+impl Default for LDtkLevelStreaming {
+    fn default() -> Self {
+        Self {
+            half_size: Vec2::new(640., 360.),
+            unload_margin: 128.,
+            max_pending_loads: 4,
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use bevy::ecs::resource::Resource;
+
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LDtkRenderBackend {
+    #[default]
+    Sprites,
+    /// Tiles are looked up through `LDtkTileChunks` instead of tile entities
+    Meshes,
+}
@@ -0,0 +1,56 @@
+use std::collections::HashSet;
+
+use bevy::{ecs::resource::Resource, math::Vec2};
+
+use crate::ldtk::prelude::LayerInstanceType;
+
+#[derive(Resource, Debug, Clone)]
+pub struct LDtkSettings {
+    pub level_background: bool,
+    /// From the last spawned level
+    pub clear_color_from_level: bool,
+    /// Also spaces the tiles stacked in a cell
+    pub layer_z_spacing: f32,
+    /// Applied as the scale of `LDtkWorld` entities
+    pub pixels_per_unit: f32,
+    pub origin: LDtkOrigin,
+    pub layer_types: HashSet<LayerInstanceType>,
+}
+
+impl Default for LDtkSettings {
+    fn default() -> Self {
+        Self {
+            level_background: true,
+            clear_color_from_level: false,
+            layer_z_spacing: 1.,
+            pixels_per_unit: 1.,
+            origin: LDtkOrigin::default(),
+            layer_types: HashSet::from([
+                LayerInstanceType::IntGrid,
+                LayerInstanceType::Entities,
+                LayerInstanceType::Tiles,
+                LayerInstanceType::AutoLayer,
+            ]),
+        }
+    }
+}
+
+/// Layers are offset so the level content stays at its world position
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LDtkOrigin {
+    #[default]
+    TopLeft,
+    BottomLeft,
+    Center,
+}
+
+impl LDtkOrigin {
+    /// y pointing up
+    pub fn offset(self, px_size: Vec2) -> Vec2 {
+        match self {
+            Self::TopLeft => Vec2::ZERO,
+            Self::BottomLeft => Vec2::new(0., -px_size.y),
+            Self::Center => Vec2::new(px_size.x, -px_size.y) / 2.,
+        }
+    }
+}
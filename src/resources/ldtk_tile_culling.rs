@@ -0,0 +1,18 @@
+use bevy::ecs::resource::Resource;
+
+/// Uses the `opaqueTiles` data LDtk caches for each tileset
+#[derive(Resource, Debug, Clone)]
+pub struct LDtkTileCulling {
+    pub within_layer: bool,
+    /// Only applied at spawn, tiles uncovered by later edits stay hidden
+    pub across_layers: bool,
+}
+
+impl Default for LDtkTileCulling {
+    fn default() -> Self {
+        Self {
+            within_layer: true,
+            across_layers: false,
+        }
+    }
+}
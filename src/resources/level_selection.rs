@@ -1,9 +1,34 @@
-use bevy::ecs::resource::Resource;
+use bevy::{
+    ecs::{component::Component, resource::Resource},
+    math::IVec2,
+};
 
-#[derive(Resource)]
+use crate::ldtk::prelude::NeighbourDirection;
+
+/// As a component of an `LDtkProjectHandle` entity it takes precedence over the resource
+#[derive(Resource, Component)]
 pub enum LevelSelection {
     ByIndices(usize, usize),
     ByIID(String),
+    /// The levels must belong to the same world
+    ByIIDs(Vec<String>),
+    AllOfWorld(usize),
+    ByIdentifier {
+        world: Option<String>,
+        level: String,
+    },
+    ByUid(i64),
+    /// `position` is in pixels relative to the world
+    ByWorldPosition {
+        world: Option<String>,
+        position: IVec2,
+    },
+    /// Relative to the current level
+    ByNeighbour(NeighbourDirection),
+    ByFieldValue {
+        identifier: String,
+        value: serde_json::Value,
+    },
 }
 
 impl LevelSelection {
@@ -14,4 +39,51 @@ impl LevelSelection {
     pub fn by_iid(iid: impl Into<String>) -> Self {
         Self::ByIID(iid.into())
     }
+
+    pub fn by_iids(iids: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self::ByIIDs(iids.into_iter().map(Into::into).collect())
+    }
+
+    pub fn all_of_world(world_index: usize) -> Self {
+        Self::AllOfWorld(world_index)
+    }
+
+    pub fn by_identifier(level: impl Into<String>) -> Self {
+        Self::ByIdentifier {
+            world: None,
+            level: level.into(),
+        }
+    }
+
+    pub fn by_world_identifier(world: impl Into<String>, level: impl Into<String>) -> Self {
+        Self::ByIdentifier {
+            world: Some(world.into()),
+            level: level.into(),
+        }
+    }
+
+    pub fn by_uid(uid: i64) -> Self {
+        Self::ByUid(uid)
+    }
+
+    pub fn by_world_position(position: IVec2) -> Self {
+        Self::ByWorldPosition {
+            world: None,
+            position,
+        }
+    }
+
+    pub fn by_neighbour(direction: NeighbourDirection) -> Self {
+        Self::ByNeighbour(direction)
+    }
+
+    pub fn by_field_value(
+        identifier: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        Self::ByFieldValue {
+            identifier: identifier.into(),
+            value: value.into(),
+        }
+    }
 }
@@ -1,7 +1,22 @@
+mod ldtk_depth_display;
+mod ldtk_editor;
+mod ldtk_level_streaming;
+mod ldtk_render_backend;
+mod ldtk_settings;
+mod ldtk_tile_culling;
 mod level_selection;
 
 pub mod prelude {
-    use crate::resources::level_selection;
+    use crate::resources::{
+        ldtk_depth_display, ldtk_editor, ldtk_level_streaming, ldtk_render_backend, ldtk_settings,
+        ldtk_tile_culling, level_selection,
+    };
 
+    pub use ldtk_depth_display::{LDtkDepthDisplay, LDtkDepthDisplayMode};
+    pub use ldtk_editor::{LDtkEditor, LDtkEditorTool};
+    pub use ldtk_level_streaming::LDtkLevelStreaming;
+    pub use ldtk_render_backend::LDtkRenderBackend;
+    pub use ldtk_settings::{LDtkOrigin, LDtkSettings};
+    pub use ldtk_tile_culling::LDtkTileCulling;
     pub use level_selection::LevelSelection;
 }
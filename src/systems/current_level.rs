@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    components::prelude::{CurrentLevel, LDtkLevel, LDtkPlayer, LDtkProjectHandle, LDtkWorld},
+    resources::prelude::LDtkSettings,
+    systems::level_streaming::{ldtk_world_px, level_world_rect},
+};
+
+pub(crate) fn update_current_level(
+    mut commands: Commands,
+    settings: Res<LDtkSettings>,
+    player_query: Query<&GlobalTransform, With<LDtkPlayer>>,
+    ldtk_project_query: Query<(Entity, Option<&CurrentLevel>, &Children), With<LDtkProjectHandle>>,
+    ldtk_world_query: Query<(&LDtkWorld, &GlobalTransform, &Children)>,
+    ldtk_level_query: Query<&LDtkLevel>,
+) {
+    let player_transform = player_query.single().ok();
+    let pixels_per_unit = settings.pixels_per_unit;
+
+    ldtk_project_query
+        .iter()
+        .for_each(|(ldtk_project_entity, current_level, project_children)| {
+            let spawned_levels = ldtk_world_query
+                .iter_many(project_children.iter())
+                .flat_map(|(ldtk_world, world_transform, children)| {
+                    let spawned_level_iids = ldtk_level_query
+                        .iter_many(children.iter())
+                        .map(|ldtk_level| ldtk_level.0.as_str())
+                        .collect::<HashSet<_>>();
+                    let player_px = player_transform
+                        .map(|player_transform| ldtk_world_px(world_transform, player_transform));
+
+                    ldtk_world
+                        .world
+                        .level_world_positions()
+                        .filter(move |(level, _)| spawned_level_iids.contains(level.iid.as_str()))
+                        .map(move |(level, world_position)| {
+                            let level_rect = level_world_rect(level, world_position);
+                            let holds_player =
+                                player_px.is_some_and(|player_px| level_rect.contains(player_px));
+                            let current_level = CurrentLevel {
+                                world_iid: ldtk_world.world.iid.clone(),
+                                level_iid: level.iid.clone(),
+                                identifier: level.identifier.clone(),
+                                bounds: Rect::new(
+                                    level_rect.min.x / pixels_per_unit,
+                                    -level_rect.min.y / pixels_per_unit,
+                                    level_rect.max.x / pixels_per_unit,
+                                    -level_rect.max.y / pixels_per_unit,
+                                ),
+                            };
+                            (holds_player, current_level)
+                        })
+                })
+                .collect::<Vec<_>>();
+
+            let is_current = |new_current_level: &CurrentLevel| {
+                current_level.is_some_and(|current_level| {
+                    current_level.level_iid == new_current_level.level_iid
+                })
+            };
+            let Some((_, new_current_level)) = spawned_levels
+                .iter()
+                .filter(|(holds_player, _)| *holds_player)
+                .find(|(_, new_current_level)| is_current(new_current_level))
+                .or_else(|| {
+                    spawned_levels
+                        .iter()
+                        .find(|(holds_player, _)| *holds_player)
+                })
+                .or_else(|| {
+                    spawned_levels
+                        .iter()
+                        .find(|(_, new_current_level)| is_current(new_current_level))
+                })
+                .or(spawned_levels.first())
+            else {
+                return;
+            };
+
+            if current_level != Some(new_current_level) {
+                commands
+                    .entity(ldtk_project_entity)
+                    .insert(new_current_level.clone());
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::ldtk::test_fixtures::sample_json;
+
+    fn spawn_project(world: &mut World, level_index: usize) -> (Entity, Entity) {
+        let sample_world = sample_json().worlds[0].clone();
+        let level_iid = sample_world.levels[level_index].iid.clone();
+        let ldtk_project_entity = world.spawn(LDtkProjectHandle(Handle::default())).id();
+        let ldtk_world_entity = world
+            .spawn((
+                LDtkWorld::new(sample_world, HashSet::from([level_iid.clone()])),
+                ChildOf(ldtk_project_entity),
+            ))
+            .id();
+        world.spawn((LDtkLevel(level_iid), ChildOf(ldtk_world_entity)));
+        (ldtk_project_entity, ldtk_world_entity)
+    }
+
+    fn current_level_iid(world: &World, ldtk_project_entity: Entity) -> Option<String> {
+        world
+            .get::<CurrentLevel>(ldtk_project_entity)
+            .map(|current_level| current_level.level_iid.clone())
+    }
+
+    #[test]
+    fn current_level_is_kept_per_project_while_no_level_is_spawned() {
+        let mut world = World::new();
+        world.init_resource::<LDtkSettings>();
+        let levels = sample_json().worlds[0].levels.clone();
+        let (first_project_entity, first_world_entity) = spawn_project(&mut world, 0);
+        let (second_project_entity, _) = spawn_project(&mut world, 1);
+
+        world.run_system_once(update_current_level).unwrap();
+        assert_eq!(
+            current_level_iid(&world, first_project_entity),
+            Some(levels[0].iid.clone())
+        );
+        assert_eq!(
+            current_level_iid(&world, second_project_entity),
+            Some(levels[1].iid.clone())
+        );
+
+        world.entity_mut(first_world_entity).despawn_children();
+        world.run_system_once(update_current_level).unwrap();
+        assert_eq!(
+            current_level_iid(&world, first_project_entity),
+            Some(levels[0].iid.clone())
+        );
+    }
+}
@@ -1,56 +1,119 @@
 use std::collections::{HashMap, HashSet};
 
-use bevy::prelude::*;
+use bevy::{prelude::*, sprite::Anchor};
 
-use crate::{components::prelude::*, ldtk::prelude::*};
+use crate::{
+    asset::prelude::LDtkProject,
+    components::prelude::*,
+    events::prelude::*,
+    ldtk::prelude::*,
+    resources::prelude::{LDtkRenderBackend, LDtkSettings, LDtkTileCulling},
+    systems::ldtk_tile_mesh::LDtkTileRenderer,
+};
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn spawn_ldtk_world(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    ldtk_world_query: Query<(Entity, &LDtkWorld), Without<Spawned>>,
+    ldtk_project_assets: Res<Assets<LDtkProject>>,
+    ldtk_project_handle_query: Query<&LDtkProjectHandle>,
+    ldtk_world_query: Query<(Entity, &LDtkWorld, &ChildOf, Option<&Children>)>,
+    ldtk_level_query: Query<&LDtkLevel>,
     mut clear_color: ResMut<ClearColor>,
+    settings: Res<LDtkSettings>,
+    mut tile_renderer: LDtkTileRenderer,
 ) {
     ldtk_world_query
         .iter()
-        .filter(|(_, ldtk_world)| {
+        .for_each(|(world_entity, ldtk_world, child_of, world_children)| {
+            let Some(ldtk_project) = ldtk_project_handle_query
+                .get(child_of.parent())
+                .ok()
+                .and_then(|ldtk_project_handle| ldtk_project_assets.get(ldtk_project_handle))
+            else {
+                return;
+            };
+
+            let spawned_levels = world_children
+                .iter()
+                .flat_map(|children| children.iter())
+                .filter_map(|level_entity| {
+                    let ldtk_level = ldtk_level_query.get(level_entity).ok()?;
+                    Some((ldtk_level.0.as_str(), level_entity))
+                })
+                .collect::<HashMap<_, _>>();
+
+            spawned_levels
+                .iter()
+                .filter(|(level_iid, _)| !ldtk_world.level_iids.contains(**level_iid))
+                .for_each(|(_, level_entity)| commands.entity(*level_entity).despawn());
+
             ldtk_world
-                .tileset_handles
-                .values()
-                .all(|(_, image_handle, _)| {
-                    asset_server
-                        .get_dependency_load_state(image_handle.id())
-                        .is_some_and(|load_state| load_state.is_loaded())
+                .world
+                .level_world_positions()
+                .filter(|(level, _)| ldtk_world.level_iids.contains(&level.iid))
+                .filter(|(level, _)| {
+                    level.layer_instances.is_some()
+                        && !spawned_levels.contains_key(level.iid.as_str())
                 })
-        })
-        .for_each(|(world_entity, ldtk_world)| {
-            let level_entity = spawn_ldtk_level(
-                &mut commands,
-                &ldtk_world.level,
-                &ldtk_world.tileset_handles,
-            );
-            commands
-                .entity(world_entity)
-                .add_child(level_entity)
-                .insert(Spawned);
+                .filter(|(level, _)| {
+                    level
+                        .get_tileset_uid_set()
+                        .iter()
+                        .filter_map(|tileset_uid| ldtk_world.tileset_handles.get(tileset_uid))
+                        .all(|(_, image_handle, _)| {
+                            asset_server
+                                .get_dependency_load_state(image_handle.id())
+                                .is_some_and(|load_state| load_state.is_loaded())
+                        })
+                })
+                .for_each(|(level, world_position)| {
+                    let level_entity = spawn_ldtk_level(
+                        &mut commands,
+                        &mut tile_renderer,
+                        &settings,
+                        &ldtk_project.json_data().defs.layers,
+                        level,
+                        world_position,
+                        &ldtk_world.tileset_handles,
+                        &ldtk_world.opaque_tiles,
+                        ldtk_world.background_handles.get(&level.iid),
+                    );
+                    commands.entity(world_entity).add_child(level_entity);
+                    commands.trigger(LevelSpawned {
+                        entity: level_entity,
+                        level_iid: level.iid.clone(),
+                    });
 
-            if let Ok(color) = Srgba::hex(&ldtk_world.level.bg_color) {
-                clear_color.0 = Color::Srgba(color);
-            }
+                    if settings.clear_color_from_level
+                        && let Ok(color) = Srgba::hex(&level.bg_color)
+                    {
+                        clear_color.0 = Color::Srgba(color);
+                    }
+                });
         });
 }
 
+#[allow(clippy::too_many_arguments)]
 fn spawn_ldtk_level(
     commands: &mut Commands,
+    tile_renderer: &mut LDtkTileRenderer,
+    settings: &LDtkSettings,
+    layer_defs: &[LayerDefinition],
     level: &Level,
+    world_position: IVec2,
     tileset_handle_map: &HashMap<i64, (usize, Handle<Image>, Handle<TextureAtlasLayout>)>,
+    opaque_tiles: &HashMap<i64, Vec<bool>>,
+    background_image_handle: Option<&Handle<Image>>,
 ) -> Entity {
-    let mut translation = Vec3::new(0., 0., level.world_depth as f32);
-    if level.world_x != -1 {
-        translation.x = level.world_x as f32;
-    }
-    if level.world_y != -1 {
-        translation.y = -level.world_y as f32;
-    }
+    let origin_offset = settings
+        .origin
+        .offset(Vec2::new(level.px_wid as f32, level.px_hei as f32));
+    let translation = Vec3::new(
+        world_position.x as f32 + origin_offset.x,
+        -world_position.y as f32 + origin_offset.y,
+        level.world_depth as f32,
+    );
     let transform = Transform::from_translation(translation);
 
     let level_entity = commands
@@ -60,18 +123,95 @@ fn spawn_ldtk_level(
             transform,
         ))
         .id();
+    commands.trigger(LevelSpawning {
+        entity: level_entity,
+        level_iid: level.iid.clone(),
+    });
+
+    if settings.level_background
+        && let Ok(color) = Srgba::hex(&level.bg_color)
+    {
+        commands.spawn((
+            Name::new("Background"),
+            LDtkLevelBackground,
+            Sprite::from_color(
+                Color::Srgba(color),
+                Vec2::new(level.px_wid as f32, level.px_hei as f32),
+            ),
+            Anchor::TOP_LEFT,
+            Transform::from_translation((-origin_offset).extend(-settings.layer_z_spacing)),
+            ChildOf(level_entity),
+        ));
+    }
+
+    if settings.level_background
+        && let Some(background_image_handle) = background_image_handle
+    {
+        ldtk_level_background_sprites(level, background_image_handle)
+            .into_iter()
+            .for_each(|(sprite, top_left_px)| {
+                commands.spawn((
+                    Name::new("Background Image"),
+                    LDtkLevelBackground,
+                    sprite,
+                    Anchor::TOP_LEFT,
+                    Transform::from_translation(
+                        (Vec2::new(top_left_px.x, -top_left_px.y) - origin_offset)
+                            .extend(-settings.layer_z_spacing / 2.),
+                    ),
+                    ChildOf(level_entity),
+                ));
+            });
+    }
 
     if let Some(layers) = &level.layer_instances {
         let mut layer_z = 0;
 
-        layers
+        // Layers are listed from the top one, so the covered cells are known before reaching
+        // the layers beneath. Parallax layers move apart from the others, so they neither cover
+        // nor get covered, and layers that won't be spawned don't cover anything.
+        let mut covered_px = HashSet::new();
+        let occluded_tiles = layers
             .iter()
-            .rev()
-            .for_each(|layer| match layer.layer_instance_type {
+            .map(|layer| {
+                let parallax = layer_defs
+                    .iter()
+                    .find(|layer_def| layer_def.uid == layer.layer_def_uid)
+                    .is_some_and(|layer_def| {
+                        layer_def.parallax_factor_x != 0. || layer_def.parallax_factor_y != 0.
+                    });
+                let spawned = settings.layer_types.contains(&layer.layer_instance_type)
+                    && layer.tileset_def_uid.is_some_and(|tileset_def_uid| {
+                        tileset_handle_map.contains_key(&tileset_def_uid)
+                    });
+                let occluded_tiles = occluded_ldtk_tiles(
+                    layer,
+                    opaque_tiles,
+                    &tile_renderer.culling,
+                    (tile_renderer.culling.across_layers && !parallax).then_some(&mut covered_px),
+                    spawned,
+                );
+                (layer.iid.as_str(), occluded_tiles)
+            })
+            .collect::<HashMap<_, _>>();
+
+        layers.iter().rev().for_each(|layer| {
+            if !settings.layer_types.contains(&layer.layer_instance_type) {
+                return;
+            }
+
+            let layer_def = layer_defs
+                .iter()
+                .find(|layer_def| layer_def.uid == layer.layer_def_uid);
+            let mut layer_translation = -origin_offset;
+
+            let spawned_layer = match layer.layer_instance_type {
                 LayerInstanceType::Entities => {
-                    // TODO:
+                    Some(spawn_ldtk_entity_layer(commands, level, layer, layer_z))
                 }
-                LayerInstanceType::IntGrid => {
+                LayerInstanceType::IntGrid
+                | LayerInstanceType::Tiles
+                | LayerInstanceType::AutoLayer => {
                     let Some(tileset_def_uid) = layer.tileset_def_uid else {
                         return;
                     };
@@ -85,44 +225,211 @@ fn spawn_ldtk_level(
                         return;
                     };
 
-                    let (layer_entity, new_layer_z) = spawn_ldtk_int_grid_layer(
+                    if let Some(layer_def) = layer_def {
+                        layer_translation += tile_pivot_offset(layer_def, *tile_size);
+                    }
+
+                    Some(spawn_ldtk_tile_layer(
                         commands,
+                        tile_renderer,
                         layer,
+                        &occluded_tiles[layer.iid.as_str()],
                         layer_z,
                         *tile_size,
-                        &tileset_image_handle,
-                        &tileset_texture_atlas_layout_handle,
-                    );
-                    commands.entity(level_entity).add_child(layer_entity);
-                    layer_z = new_layer_z;
-                }
-                LayerInstanceType::Tiles => {
-                    // TODO:
+                        tileset_image_handle,
+                        tileset_texture_atlas_layout_handle,
+                    ))
                 }
-                LayerInstanceType::AutoLayer => {
-                    // TODO:
+            };
+
+            if let Some((layer_entity, new_layer_z)) = spawned_layer {
+                commands
+                    .entity(layer_entity)
+                    .insert(Transform::from_translation(
+                        layer_translation.extend(layer_z as f32 * settings.layer_z_spacing),
+                    ));
+                if let Some(layer_def) = layer_def {
+                    if layer_def.parallax_factor_x != 0. || layer_def.parallax_factor_y != 0. {
+                        commands.entity(layer_entity).insert(LDtkParallax {
+                            factor: Vec2::new(
+                                layer_def.parallax_factor_x as f32,
+                                layer_def.parallax_factor_y as f32,
+                            ),
+                            scaling: layer_def.parallax_scaling,
+                            rest_translation: layer_translation,
+                            level_center: -origin_offset
+                                + Vec2::new(level.px_wid as f32, -level.px_hei as f32) / 2.,
+                        });
+                    }
+                    commands
+                        .entity(layer_entity)
+                        .insert(LDtkLayerDef(layer_def.clone()));
                 }
-            });
+                commands.entity(level_entity).add_child(layer_entity);
+                commands.trigger(LayerSpawned {
+                    entity: layer_entity,
+                    level_iid: level.iid.clone(),
+                    layer_iid: layer.iid.clone(),
+                });
+                layer_z = new_layer_z;
+            }
+        });
     };
 
     level_entity
 }
 
-fn spawn_ldtk_int_grid_layer(
+/// Follows the `tile_pivot_x/y` of the layer definition when the tileset grid differs
+fn tile_pivot_offset(layer_def: &LayerDefinition, tile_size: usize) -> Vec2 {
+    let size_difference = (layer_def.grid_size - tile_size as i64) as f32;
+    Vec2::new(
+        size_difference * (layer_def.tile_pivot_x as f32 - 0.5),
+        -size_difference * (layer_def.tile_pivot_y as f32 - 0.5),
+    )
+}
+
+/// With their top-left position in the level, repeated images are tiled from `top_left_px`
+fn ldtk_level_background_sprites(
+    level: &Level,
+    background_image_handle: &Handle<Image>,
+) -> Vec<(Sprite, Vec2)> {
+    let level_size = Vec2::new(level.px_wid as f32, level.px_hei as f32);
+    let bg_pos = level.bg_pos.as_ref().and_then(|bg_pos| {
+        let &[crop_x, crop_y, crop_width, crop_height] = bg_pos.crop_rect.as_slice() else {
+            return None;
+        };
+        let &[scale_x, scale_y] = bg_pos.scale.as_slice() else {
+            return None;
+        };
+        let crop_rect = Rect::new(
+            crop_x as f32,
+            crop_y as f32,
+            (crop_x + crop_width) as f32,
+            (crop_y + crop_height) as f32,
+        );
+        Some((
+            crop_rect,
+            Vec2::new(scale_x as f32, scale_y as f32),
+            bg_pos.top_left_px.as_vec2(),
+        ))
+    });
+
+    match (&level.level_bg_pos, bg_pos) {
+        (Some(BgPos::Repeat), Some((crop_rect, _, top_left_px))) => {
+            let segments_x = repeat_segments(level_size.x, crop_rect.width(), top_left_px.x);
+            let segments_y = repeat_segments(level_size.y, crop_rect.height(), top_left_px.y);
+            segments_y
+                .iter()
+                .flat_map(|segment_y| {
+                    segments_x
+                        .iter()
+                        .map(move |segment_x| (segment_x, segment_y))
+                })
+                .map(|(segment_x, segment_y)| {
+                    let texture_min =
+                        crop_rect.min + Vec2::new(segment_x.texture_start, segment_y.texture_start);
+                    let texture_size = Vec2::new(
+                        segment_x.texture_len(crop_rect.width()),
+                        segment_y.texture_len(crop_rect.height()),
+                    );
+                    let sprite = Sprite {
+                        image: background_image_handle.clone(),
+                        rect: Some(Rect::from_corners(texture_min, texture_min + texture_size)),
+                        custom_size: Some(Vec2::new(segment_x.len, segment_y.len)),
+                        image_mode: SpriteImageMode::Tiled {
+                            tile_x: segment_x.tiled,
+                            tile_y: segment_y.tiled,
+                            stretch_value: 1.,
+                        },
+                        ..default()
+                    };
+                    (sprite, Vec2::new(segment_x.start, segment_y.start))
+                })
+                .collect()
+        }
+        (Some(BgPos::Repeat), None) => {
+            let sprite = Sprite {
+                image: background_image_handle.clone(),
+                custom_size: Some(level_size),
+                image_mode: SpriteImageMode::Tiled {
+                    tile_x: true,
+                    tile_y: true,
+                    stretch_value: 1.,
+                },
+                ..default()
+            };
+            vec![(sprite, Vec2::ZERO)]
+        }
+        (_, Some((crop_rect, scale, top_left_px))) => {
+            let sprite = Sprite {
+                image: background_image_handle.clone(),
+                rect: Some(crop_rect),
+                custom_size: Some(crop_rect.size() * scale),
+                ..default()
+            };
+            vec![(sprite, top_left_px)]
+        }
+        (_, None) => Vec::new(),
+    }
+}
+
+struct RepeatSegment {
+    start: f32,
+    len: f32,
+    texture_start: f32,
+    tiled: bool,
+}
+
+impl RepeatSegment {
+    fn texture_len(&self, image_len: f32) -> f32 {
+        if self.tiled { image_len } else { self.len }
+    }
+}
+
+/// The image end cut by the level edge, then the image repeated from `offset`
+fn repeat_segments(level_len: f32, image_len: f32, offset: f32) -> Vec<RepeatSegment> {
+    let cut_len = offset.rem_euclid(image_len);
+    [
+        RepeatSegment {
+            start: 0.,
+            len: cut_len.min(level_len),
+            texture_start: image_len - cut_len,
+            tiled: false,
+        },
+        RepeatSegment {
+            start: cut_len,
+            len: level_len - cut_len,
+            texture_start: 0.,
+            tiled: true,
+        },
+    ]
+    .into_iter()
+    .filter(|segment| segment.len > 0.)
+    .collect()
+}
+
+fn spawn_ldtk_layer_entity(commands: &mut Commands, layer: &LayerInstance) -> Entity {
+    commands
+        .spawn((
+            Name::new(layer.identifier.clone()),
+            LDtkLayer(layer.iid.clone()),
+        ))
+        .insert_if(Visibility::Hidden, || !layer.visible)
+        .id()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn spawn_ldtk_tile_layer(
     commands: &mut Commands,
+    tile_renderer: &mut LDtkTileRenderer,
     layer: &LayerInstance,
+    occluded_tiles: &HashSet<(IVec2, usize)>,
     layer_z: i64,
     tile_size: usize,
     tileset_image_handle: &Handle<Image>,
     tileset_texture_atlas_layout_handle: &Handle<TextureAtlasLayout>,
 ) -> (Entity, i64) {
-    let layer_entity = commands
-        .spawn((
-            Name::new(layer.identifier.clone()),
-            LDtkLayer(layer.iid.clone()),
-        ))
-        .insert_if(Visibility::Hidden, || !layer.visible)
-        .id();
+    let layer_entity = spawn_ldtk_layer_entity(commands, layer);
 
     let entended_layers = extend_ldtk_layer(
         layer
@@ -136,53 +443,293 @@ fn spawn_ldtk_int_grid_layer(
     );
     let extended_layer_count = entended_layers.len();
 
-    let tile_entities = entended_layers
-        .into_iter()
-        .enumerate()
-        .flat_map(|(layer_index, tiles)| std::iter::repeat(layer_index).zip(tiles.into_iter()))
-        .map(|(layer_index, tile)| {
-            let x = (tile.px.x as i64 + layer.px_total_offset_x) as f32;
-            let y = (-tile.px.y as i64 + layer.px_total_offset_y) as f32;
-            let z = (layer_z + layer_index as i64) as f32;
-            let transform = Transform::from_xyz(x, y, z);
-
-            let mut sprite = Sprite::from_atlas_image(
-                tileset_image_handle.clone(),
-                TextureAtlas {
-                    layout: tileset_texture_atlas_layout_handle.clone(),
-                    index: tile.t as usize,
-                },
-            );
+    if *tile_renderer.backend == LDtkRenderBackend::Meshes {
+        tile_renderer.spawn_tile_chunks(
+            commands,
+            layer_entity,
+            layer,
+            occluded_tiles,
+            tileset_image_handle,
+            tileset_texture_atlas_layout_handle,
+        );
+        return (layer_entity, layer_z + extended_layer_count as i64);
+    }
 
-            sprite.color.set_alpha((tile.a * layer.opacity) as f32);
-            sprite.flip_x = tile.f & 0b01 != 0;
-            sprite.flip_y = tile.f & 0b10 != 0;
+    commands
+        .entity(layer_entity)
+        .insert(LDtkTileStorage::default());
 
-            let ldtk_grid_coord = LDtkGridCoord(IVec2::new(
-                tile.px.x / layer.grid_size as i32,
-                tile.px.y / layer.grid_size as i32,
+    entended_layers
+        .into_iter()
+        .enumerate()
+        .flat_map(|(layer_index, tiles)| std::iter::repeat(layer_index).zip(tiles))
+        .filter(|&(layer_index, tile)| !occluded_tiles.contains(&(tile.px, layer_index)))
+        .for_each(|(layer_index, tile)| {
+            commands.spawn(ldtk_tile_bundle(
+                layer_entity,
+                layer,
+                tile,
+                layer_index,
+                tile_renderer.stack_z_spacing(),
+                tileset_image_handle,
+                tileset_texture_atlas_layout_handle,
             ));
+        });
 
-            let bevy_grid_coord = BevyGridCoord(IVec2::new(
-                tile.px.x / layer.grid_size as i32,
-                layer.c_hei as i32 - tile.px.y / layer.grid_size as i32 - 1,
-            ));
+    (layer_entity, layer_z + extended_layer_count as i64)
+}
+
+fn spawn_ldtk_entity_layer(
+    commands: &mut Commands,
+    level: &Level,
+    layer: &LayerInstance,
+    layer_z: i64,
+) -> (Entity, i64) {
+    let layer_entity = spawn_ldtk_layer_entity(commands, layer);
 
+    let entity_instance_entities = layer
+        .entity_instances
+        .iter()
+        .map(|entity_instance| {
             commands
-                .spawn((
-                    transform,
-                    sprite,
-                    LDtkTile,
-                    ldtk_grid_coord,
-                    bevy_grid_coord,
-                ))
+                .spawn(ldtk_entity_bundle(layer, entity_instance))
                 .id()
         })
         .collect::<Vec<_>>();
 
-    commands.entity(layer_entity).add_children(&tile_entities);
+    commands
+        .entity(layer_entity)
+        .add_children(&entity_instance_entities);
 
-    (layer_entity, layer_z + extended_layer_count as i64)
+    layer
+        .entity_instances
+        .iter()
+        .zip(entity_instance_entities)
+        .for_each(|(entity_instance, entity)| {
+            commands.trigger(EntityInstanceSpawned {
+                entity,
+                level_iid: level.iid.clone(),
+                layer_iid: layer.iid.clone(),
+                entity_iid: entity_instance.iid.clone(),
+            });
+        });
+
+    (layer_entity, layer_z + 1)
+}
+
+pub(crate) fn ldtk_tile_bundle(
+    layer_entity: Entity,
+    layer: &LayerInstance,
+    tile: &TileInstance,
+    stack_index: usize,
+    stack_z_spacing: f32,
+    tileset_image_handle: &Handle<Image>,
+    tileset_texture_atlas_layout_handle: &Handle<TextureAtlasLayout>,
+) -> impl Bundle {
+    let transform = Transform::from_translation(
+        layer
+            .px_to_translation(tile.px)
+            .extend(stack_index as f32 * stack_z_spacing),
+    );
+
+    let mut sprite = Sprite::from_atlas_image(
+        tileset_image_handle.clone(),
+        TextureAtlas {
+            layout: tileset_texture_atlas_layout_handle.clone(),
+            index: tile.t as usize,
+        },
+    );
+
+    sprite.color.set_alpha((tile.a * layer.opacity) as f32);
+    sprite.flip_x = tile.f & 0b01 != 0;
+    sprite.flip_y = tile.f & 0b10 != 0;
+
+    let ldtk_grid_coord = LDtkGridCoord(tile_grid_coord(layer, tile));
+
+    let bevy_grid_coord = BevyGridCoord(IVec2::new(
+        tile.px.x / layer.grid_size as i32,
+        layer.c_hei as i32 - tile.px.y / layer.grid_size as i32 - 1,
+    ));
+
+    (
+        transform,
+        sprite,
+        LDtkTile,
+        ldtk_grid_coord,
+        bevy_grid_coord,
+        LDtkStackIndex(stack_index),
+        ChildOf(layer_entity),
+    )
+}
+
+pub(crate) fn tile_grid_coord(layer: &LayerInstance, tile: &TileInstance) -> IVec2 {
+    IVec2::new(
+        tile.px.x / layer.grid_size as i32,
+        tile.px.y / layer.grid_size as i32,
+    )
+}
+
+/// Layers rendered as meshes have their chunks baked again
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn respawn_ldtk_tiles(
+    commands: &mut Commands,
+    tile_renderer: &mut LDtkTileRenderer,
+    layer_entity: Entity,
+    ldtk_tile_chunks: Option<&LDtkTileChunks>,
+    layer: &LayerInstance,
+    grid_coords: &HashSet<IVec2>,
+    tileset_handle_map: &HashMap<i64, (usize, Handle<Image>, Handle<TextureAtlasLayout>)>,
+    opaque_tiles: &HashMap<i64, Vec<bool>>,
+) {
+    let occluded_tiles =
+        occluded_ldtk_tiles(layer, opaque_tiles, &tile_renderer.culling, None, false);
+
+    if let Some(ldtk_tile_chunks) = ldtk_tile_chunks {
+        tile_renderer.rebake_tile_chunks(
+            commands,
+            ldtk_tile_chunks,
+            layer,
+            &occluded_tiles,
+            grid_coords,
+        );
+        return;
+    }
+
+    // The stacks are read when the command is applied, so the tiles spawned by an earlier
+    // respawn of the same cells in this frame are despawned too
+    let cleared_grid_coords = grid_coords.clone();
+    commands.queue(move |world: &mut bevy::ecs::world::World| {
+        let Some(ldtk_tile_storage) = world.get::<LDtkTileStorage>(layer_entity) else {
+            return;
+        };
+        let tile_entities = cleared_grid_coords
+            .iter()
+            .flat_map(|grid_coord| ldtk_tile_storage.stack(&LDtkGridCoord(*grid_coord)))
+            .collect::<Vec<_>>();
+        tile_entities.into_iter().for_each(|tile_entity| {
+            if let Ok(tile_entity) = world.get_entity_mut(tile_entity) {
+                tile_entity.despawn();
+            }
+        });
+    });
+
+    let Some((_, tileset_image_handle, tileset_texture_atlas_layout_handle)) = layer
+        .tileset_def_uid
+        .and_then(|tileset_def_uid| tileset_handle_map.get(&tileset_def_uid))
+    else {
+        return;
+    };
+
+    let stack_z_spacing = tile_renderer.stack_z_spacing();
+    let mut stack_sizes: HashMap<IVec2, usize> = HashMap::new();
+    layer
+        .grid_tiles
+        .iter()
+        .chain(layer.auto_layer_tiles.iter())
+        .filter(|tile| grid_coords.contains(&tile_grid_coord(layer, tile)))
+        .for_each(|tile| {
+            let stack_size = stack_sizes.entry(tile.px).or_default();
+            let stack_index = *stack_size;
+            *stack_size += 1;
+
+            if occluded_tiles.contains(&(tile.px, stack_index)) {
+                return;
+            }
+
+            commands.spawn(ldtk_tile_bundle(
+                layer_entity,
+                layer,
+                tile,
+                stack_index,
+                stack_z_spacing,
+                tileset_image_handle,
+                tileset_texture_atlas_layout_handle,
+            ));
+        });
+}
+
+/// As `px` and stack index. Layers that are `covering` add their opaque cells to `covered_px`.
+pub(crate) fn occluded_ldtk_tiles(
+    layer: &LayerInstance,
+    opaque_tiles: &HashMap<i64, Vec<bool>>,
+    tile_culling: &LDtkTileCulling,
+    mut covered_px: Option<&mut HashSet<(IVec2, i64)>>,
+    covering: bool,
+) -> HashSet<(IVec2, usize)> {
+    let opaque_tiles = layer
+        .tileset_def_uid
+        .and_then(|tileset_def_uid| opaque_tiles.get(&tileset_def_uid));
+    let is_opaque = |tile: &TileInstance| {
+        layer.visible
+            && layer.opacity >= 1.
+            && tile.a >= 1.
+            && opaque_tiles
+                .and_then(|opaque_tiles| opaque_tiles.get(tile.t as usize))
+                .is_some_and(|opaque| *opaque)
+    };
+    let layer_offset = IVec2::new(
+        layer.px_total_offset_x as i32,
+        layer.px_total_offset_y as i32,
+    );
+
+    let mut stack_sizes: HashMap<IVec2, usize> = HashMap::new();
+    let stacked_tiles = layer
+        .grid_tiles
+        .iter()
+        .chain(layer.auto_layer_tiles.iter())
+        .map(|tile| {
+            let stack_size = stack_sizes.entry(tile.px).or_default();
+            let stack_index = *stack_size;
+            *stack_size += 1;
+            (stack_index, tile)
+        })
+        .collect::<Vec<_>>();
+
+    let mut top_opaque_tiles: HashMap<IVec2, usize> = HashMap::new();
+    if tile_culling.within_layer {
+        stacked_tiles
+            .iter()
+            .filter(|(_, tile)| is_opaque(tile))
+            .for_each(|&(stack_index, tile)| {
+                top_opaque_tiles.insert(tile.px, stack_index);
+            });
+    }
+
+    let occluded_tiles = stacked_tiles
+        .iter()
+        .filter(|&&(stack_index, tile)| {
+            top_opaque_tiles
+                .get(&tile.px)
+                .is_some_and(|&top_stack_index| stack_index < top_stack_index)
+                || covered_px.as_ref().is_some_and(|covered_px| {
+                    covered_px.contains(&(tile.px + layer_offset, layer.grid_size))
+                })
+        })
+        .map(|&(stack_index, tile)| (tile.px, stack_index))
+        .collect();
+
+    if covering && let Some(covered_px) = &mut covered_px {
+        covered_px.extend(
+            stacked_tiles
+                .iter()
+                .filter(|(_, tile)| is_opaque(tile))
+                .map(|(_, tile)| (tile.px + layer_offset, layer.grid_size)),
+        );
+    }
+
+    occluded_tiles
+}
+
+pub(crate) fn ldtk_entity_bundle(
+    layer: &LayerInstance,
+    entity_instance: &EntityInstance,
+) -> impl Bundle {
+    (
+        Name::new(entity_instance.identifier.clone()),
+        LDtkEntity(entity_instance.iid.clone()),
+        Transform::from_translation(layer.px_to_translation(entity_instance.px).extend(0.)),
+        LDtkGridCoord(entity_instance.grid),
+    )
 }
 
 fn extend_ldtk_layer<'a, I: Iterator<Item = &'a TileInstance>>(
@@ -217,3 +764,346 @@ pub fn despawn_all_ldtk_worlds(
         .iter()
         .for_each(|world_entity| commands.entity(world_entity).despawn());
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::{
+        ldtk::test_fixtures::{sample_json, sample_layer, sample_tileset_handles, tile_world},
+        resources::prelude::LDtkSettings,
+    };
+
+    fn respawn_layer(layer_entity: Entity, times: usize) -> impl FnMut(Commands, LDtkTileRenderer) {
+        let layer = sample_layer();
+        let grid_coords = (0..layer.c_hei as i32)
+            .flat_map(|y| (0..layer.c_wid as i32).map(move |x| IVec2::new(x, y)))
+            .collect::<HashSet<_>>();
+
+        move |mut commands, mut tile_renderer| {
+            for _ in 0..times {
+                respawn_ldtk_tiles(
+                    &mut commands,
+                    &mut tile_renderer,
+                    layer_entity,
+                    None,
+                    &layer,
+                    &grid_coords,
+                    &sample_tileset_handles(),
+                    &HashMap::new(),
+                );
+            }
+        }
+    }
+
+    fn stored_and_spawned_tiles(
+        world: &mut bevy::ecs::world::World,
+        layer_entity: Entity,
+    ) -> (HashSet<Entity>, HashSet<Entity>) {
+        let stored = world
+            .get::<LDtkTileStorage>(layer_entity)
+            .unwrap()
+            .iter()
+            .map(|(_, _, tile_entity)| tile_entity)
+            .collect();
+        let spawned = world
+            .query_filtered::<Entity, With<LDtkTile>>()
+            .iter(world)
+            .collect();
+        (stored, spawned)
+    }
+
+    #[test]
+    fn respawning_a_layer_twice_in_a_frame_keeps_storage_consistent() {
+        let mut world = tile_world();
+        let layer_entity = world.spawn(LDtkTileStorage::default()).id();
+
+        world
+            .run_system_once(respawn_layer(layer_entity, 2))
+            .unwrap();
+        world
+            .run_system_once(respawn_layer(layer_entity, 2))
+            .unwrap();
+
+        let (stored, spawned) = stored_and_spawned_tiles(&mut world, layer_entity);
+        assert_eq!(stored.len(), sample_layer().auto_layer_tiles.len());
+        assert_eq!(spawned, stored);
+    }
+
+    #[test]
+    fn storing_a_tile_over_another_despawns_it() {
+        let mut world = tile_world();
+        let layer_entity = world.spawn(LDtkTileStorage::default()).id();
+        let tile = || {
+            (
+                LDtkTile,
+                LDtkGridCoord(IVec2::new(1, 2)),
+                LDtkStackIndex(0),
+                ChildOf(layer_entity),
+            )
+        };
+
+        let first_tile_entity = world.spawn(tile()).id();
+        let second_tile_entity = world.spawn(tile()).id();
+        world.flush();
+
+        let ldtk_tile_storage = world.get::<LDtkTileStorage>(layer_entity).unwrap();
+        assert_eq!(
+            ldtk_tile_storage.get(&LDtkGridCoord(IVec2::new(1, 2)), 0),
+            Some(second_tile_entity)
+        );
+        assert_eq!(ldtk_tile_storage.len(), 1);
+        assert!(world.get_entity(first_tile_entity).is_err());
+    }
+
+    fn tiles_beneath(top_layer: Option<LayerInstance>, layer_defs: Vec<LayerDefinition>) -> usize {
+        let mut world = tile_world();
+        world.insert_resource(LDtkTileCulling {
+            within_layer: true,
+            across_layers: true,
+        });
+        let mut level = sample_json().worlds[0].levels[0].clone();
+        level.layer_instances = Some(top_layer.into_iter().chain([sample_layer()]).collect());
+
+        world
+            .run_system_once(
+                move |mut commands: Commands, mut tile_renderer: LDtkTileRenderer| {
+                    spawn_ldtk_level(
+                        &mut commands,
+                        &mut tile_renderer,
+                        &LDtkSettings::default(),
+                        &layer_defs,
+                        &level,
+                        IVec2::ZERO,
+                        &sample_tileset_handles(),
+                        &HashMap::from([(1, vec![true; 16])]),
+                        None,
+                    );
+                },
+            )
+            .unwrap();
+
+        let sample_layer_iid = sample_layer().iid;
+        world
+            .query::<(&LDtkTile, &ChildOf)>()
+            .iter(&world)
+            .filter(|(_, child_of)| {
+                world.get::<LDtkLayer>(child_of.parent()).unwrap().0 == sample_layer_iid
+            })
+            .count()
+    }
+
+    #[test]
+    fn only_spawned_layers_without_parallax_cover_tiles_beneath() {
+        let layer_defs = sample_json().defs.layers;
+        let uncovered_tiles = tiles_beneath(None, layer_defs.clone());
+        assert!(uncovered_tiles > 0);
+
+        let mut top_layer = sample_layer();
+        top_layer.iid = "top".to_string();
+        assert_eq!(
+            tiles_beneath(Some(top_layer.clone()), layer_defs.clone()),
+            0
+        );
+
+        let mut hidden_layer = top_layer.clone();
+        hidden_layer.tileset_def_uid = Some(99);
+        assert_eq!(
+            tiles_beneath(Some(hidden_layer), layer_defs.clone()),
+            uncovered_tiles
+        );
+
+        let mut parallax_layer_def = layer_defs[0].clone();
+        parallax_layer_def.uid = 99;
+        parallax_layer_def.parallax_factor_x = 0.5;
+        let mut parallax_layer = top_layer;
+        parallax_layer.layer_def_uid = 99;
+        assert_eq!(
+            tiles_beneath(
+                Some(parallax_layer),
+                layer_defs.into_iter().chain([parallax_layer_def]).collect()
+            ),
+            uncovered_tiles
+        );
+    }
+
+    #[test]
+    fn stacked_tiles_follow_layer_z_spacing() {
+        let mut world = tile_world();
+        let settings = LDtkSettings {
+            layer_z_spacing: 0.1,
+            ..default()
+        };
+        world.insert_resource(settings.clone());
+        let level = sample_json().worlds[0].levels[0].clone();
+        let layer_defs = sample_json().defs.layers;
+
+        world
+            .run_system_once(
+                move |mut commands: Commands, mut tile_renderer: LDtkTileRenderer| {
+                    spawn_ldtk_level(
+                        &mut commands,
+                        &mut tile_renderer,
+                        &settings,
+                        &layer_defs,
+                        &level,
+                        IVec2::ZERO,
+                        &sample_tileset_handles(),
+                        &HashMap::new(),
+                        None,
+                    );
+                },
+            )
+            .unwrap();
+
+        let mut tiles = world.query::<(&LDtkStackIndex, &Transform)>();
+        assert!(tiles.iter(&world).any(|(stack_index, _)| stack_index.0 > 0));
+        tiles.iter(&world).for_each(|(stack_index, transform)| {
+            assert_eq!(transform.translation.z, stack_index.0 as f32 * 0.1);
+        });
+    }
+
+    /// Texture rect, size, tiling and top-left position of a background sprite
+    type BackgroundSprite = (Rect, Vec2, Option<(bool, bool)>, Vec2);
+
+    fn background_sprites(
+        level_bg_pos: BgPos,
+        crop_rect: [f64; 4],
+        scale: [f64; 2],
+        top_left_px: IVec2,
+    ) -> Vec<BackgroundSprite> {
+        let mut level = sample_json().worlds[0].levels[0].clone();
+        level.level_bg_pos = Some(level_bg_pos);
+        level.bg_pos = Some(LevelBackgroundPosition {
+            crop_rect: crop_rect.to_vec(),
+            scale: scale.to_vec(),
+            top_left_px,
+            unknown_fields: Default::default(),
+        });
+
+        ldtk_level_background_sprites(&level, &Handle::default())
+            .into_iter()
+            .map(|(sprite, top_left_px)| {
+                let tiling = match sprite.image_mode {
+                    SpriteImageMode::Tiled { tile_x, tile_y, .. } => Some((tile_x, tile_y)),
+                    _ => None,
+                };
+                (
+                    sprite.rect.unwrap(),
+                    sprite.custom_size.unwrap(),
+                    tiling,
+                    top_left_px,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unscaled_background_is_cropped() {
+        assert_eq!(
+            background_sprites(BgPos::Unscaled, [26., 9., 48., 32.], [1., 1.], IVec2::ZERO),
+            vec![(
+                Rect::new(26., 9., 74., 41.),
+                Vec2::new(48., 32.),
+                None,
+                Vec2::ZERO
+            )]
+        );
+    }
+
+    #[test]
+    fn contained_background_is_scaled_and_offset() {
+        assert_eq!(
+            background_sprites(
+                BgPos::Contain,
+                [0., 0., 96., 32.],
+                [0.5, 0.5],
+                IVec2::new(0, 8)
+            ),
+            vec![(
+                Rect::new(0., 0., 96., 32.),
+                Vec2::new(48., 16.),
+                None,
+                Vec2::new(0., 8.)
+            )]
+        );
+    }
+
+    #[test]
+    fn covering_background_is_cropped_and_scaled() {
+        assert_eq!(
+            background_sprites(BgPos::Cover, [6., 0., 12., 8.], [4., 4.], IVec2::ZERO),
+            vec![(
+                Rect::new(6., 0., 18., 8.),
+                Vec2::new(48., 32.),
+                None,
+                Vec2::ZERO
+            )]
+        );
+    }
+
+    #[test]
+    fn dirty_covering_background_is_stretched() {
+        assert_eq!(
+            background_sprites(BgPos::CoverDirty, [0., 0., 24., 16.], [2., 2.], IVec2::ZERO),
+            vec![(
+                Rect::new(0., 0., 24., 16.),
+                Vec2::new(48., 32.),
+                None,
+                Vec2::ZERO
+            )]
+        );
+    }
+
+    #[test]
+    fn repeated_background_is_tiled_from_its_top_left_corner() {
+        assert_eq!(
+            background_sprites(
+                BgPos::Repeat,
+                [0., 0., 20., 20.],
+                [1., 1.],
+                IVec2::new(5, -3)
+            ),
+            vec![
+                (
+                    Rect::new(15., 3., 20., 20.),
+                    Vec2::new(5., 17.),
+                    Some((false, false)),
+                    Vec2::ZERO
+                ),
+                (
+                    Rect::new(0., 3., 20., 20.),
+                    Vec2::new(43., 17.),
+                    Some((true, false)),
+                    Vec2::new(5., 0.)
+                ),
+                (
+                    Rect::new(15., 0., 20., 20.),
+                    Vec2::new(5., 15.),
+                    Some((false, true)),
+                    Vec2::new(0., 17.)
+                ),
+                (
+                    Rect::new(0., 0., 20., 20.),
+                    Vec2::new(43., 15.),
+                    Some((true, true)),
+                    Vec2::new(5., 17.)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn aligned_repeated_background_is_a_single_sprite() {
+        assert_eq!(
+            background_sprites(BgPos::Repeat, [0., 0., 16., 16.], [1., 1.], IVec2::ZERO),
+            vec![(
+                Rect::new(0., 0., 16., 16.),
+                Vec2::new(48., 32.),
+                Some((true, true)),
+                Vec2::ZERO
+            )]
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+
+use crate::{
+    asset::prelude::LDtkProject,
+    components::prelude::*,
+    ldtk::prelude::*,
+    messages::prelude::LDtkIntGridChanged,
+    systems::{ldtk::respawn_ldtk_tiles, ldtk_tile_mesh::LDtkTileRenderer},
+};
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn refresh_ldtk_auto_layers(
+    mut commands: Commands,
+    mut int_grid_changes: MessageReader<LDtkIntGridChanged>,
+    ldtk_project_assets: Res<Assets<LDtkProject>>,
+    ldtk_project_handle_query: Query<&LDtkProjectHandle>,
+    mut ldtk_world_query: Query<(&mut LDtkWorld, &ChildOf)>,
+    ldtk_layer_query: Query<(Entity, &LDtkLayer, Option<&LDtkTileChunks>)>,
+    mut tile_renderer: LDtkTileRenderer,
+) {
+    int_grid_changes.read().for_each(|int_grid_change| {
+        let Some((mut ldtk_world, child_of)) =
+            ldtk_world_query.iter_mut().find(|(ldtk_world, _)| {
+                ldtk_world
+                    .level_with_layer(&int_grid_change.layer_iid)
+                    .is_some()
+            })
+        else {
+            return;
+        };
+        let ldtk_world = &mut *ldtk_world;
+
+        let Some(ldtk_project) = ldtk_project_handle_query
+            .get(child_of.parent())
+            .ok()
+            .and_then(|ldtk_project_handle| ldtk_project_assets.get(ldtk_project_handle))
+        else {
+            return;
+        };
+
+        let Some(level) = ldtk_world
+            .world
+            .levels
+            .iter_mut()
+            .find(|level| level.layer_instance(&int_grid_change.layer_iid).is_some())
+        else {
+            return;
+        };
+
+        let refreshed_layers = refresh_dependent_auto_layers(
+            level,
+            &ldtk_project.json_data().defs,
+            &int_grid_change.layer_iid,
+            int_grid_change.region,
+        );
+
+        refreshed_layers
+            .iter()
+            .for_each(|(layer_iid, changed_grid_coords)| {
+                let Some(layer) = level.layer_instance(layer_iid) else {
+                    return;
+                };
+                let Some((layer_entity, _, ldtk_tile_chunks)) = ldtk_layer_query
+                    .iter()
+                    .find(|(_, ldtk_layer, _)| &ldtk_layer.0 == layer_iid)
+                else {
+                    return;
+                };
+
+                respawn_ldtk_tiles(
+                    &mut commands,
+                    &mut tile_renderer,
+                    layer_entity,
+                    ldtk_tile_chunks,
+                    layer,
+                    changed_grid_coords,
+                    &ldtk_world.tileset_handles,
+                    &ldtk_world.opaque_tiles,
+                );
+            });
+    });
+}
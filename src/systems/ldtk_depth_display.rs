@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::prelude::{LDtkBaseAlpha, LDtkLevel, LDtkTile, LDtkTileChunk, LDtkWorld},
+    resources::prelude::{LDtkDepthDisplay, LDtkDepthDisplayMode},
+};
+
+type AddedTileQuery<'w, 's> = Query<'w, 's, Entity, Or<(Added<LDtkTile>, Added<LDtkTileChunk>)>>;
+
+/// Also fades the tiles spawned in an existing level
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn apply_ldtk_depth_display(
+    mut commands: Commands,
+    depth_display: Res<LDtkDepthDisplay>,
+    added_level_query: Query<(), Added<LDtkLevel>>,
+    added_tile_query: AddedTileQuery,
+    ldtk_world_query: Query<&LDtkWorld>,
+    mut ldtk_level_query: Query<(Entity, &LDtkLevel, &ChildOf, &mut Visibility)>,
+    children_query: Query<&Children>,
+    child_of_query: Query<&ChildOf>,
+    mut sprite_query: Query<(&mut Sprite, Option<&LDtkBaseAlpha>)>,
+    color_material_query: Query<&MeshMaterial2d<ColorMaterial>>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let level_depth = |ldtk_level: &LDtkLevel, child_of: &ChildOf| {
+        ldtk_world_query
+            .get(child_of.parent())
+            .ok()
+            .and_then(|ldtk_world| ldtk_world.level(&ldtk_level.0))
+            .map(|level| level.world_depth)
+    };
+
+    let mut fade = |entity: Entity, alpha: f32| {
+        if let Ok((mut sprite, base_alpha)) = sprite_query.get_mut(entity) {
+            let base_alpha = match base_alpha {
+                Some(base_alpha) => base_alpha.0,
+                None => {
+                    let base_alpha = sprite.color.alpha();
+                    commands.entity(entity).insert(LDtkBaseAlpha(base_alpha));
+                    base_alpha
+                }
+            };
+            sprite.color.set_alpha(base_alpha * alpha);
+        }
+        if let Some(color_material) = color_material_query
+            .get(entity)
+            .ok()
+            .and_then(|material| color_materials.get_mut(&material.0))
+        {
+            color_material.color.set_alpha(alpha);
+        }
+    };
+
+    if !depth_display.is_changed() && added_level_query.is_empty() {
+        added_tile_query.iter().for_each(|tile_entity| {
+            let Some((ldtk_level, child_of)) = child_of_query
+                .iter_ancestors(tile_entity)
+                .find_map(|ancestor| ldtk_level_query.get(ancestor).ok())
+                .map(|(_, ldtk_level, child_of, _)| (ldtk_level, child_of))
+            else {
+                return;
+            };
+            if let Some(world_depth) = level_depth(ldtk_level, child_of) {
+                fade(tile_entity, depth_display_of(&depth_display, world_depth).1);
+            }
+        });
+        return;
+    }
+
+    ldtk_level_query
+        .iter_mut()
+        .for_each(|(level_entity, ldtk_level, child_of, mut visibility)| {
+            let Some(world_depth) = level_depth(ldtk_level, child_of) else {
+                return;
+            };
+
+            let (hidden, alpha) = depth_display_of(&depth_display, world_depth);
+            visibility.set_if_neq(if hidden {
+                Visibility::Hidden
+            } else {
+                Visibility::Inherited
+            });
+
+            children_query
+                .iter_descendants(level_entity)
+                .for_each(|entity| fade(entity, alpha));
+        });
+}
+
+/// Whether the level is hidden, and the alpha of its content
+fn depth_display_of(depth_display: &LDtkDepthDisplay, world_depth: i64) -> (bool, f32) {
+    let is_active = world_depth == depth_display.active_depth;
+    match depth_display.mode {
+        LDtkDepthDisplayMode::All => (false, 1.),
+        LDtkDepthDisplayMode::ActiveOnly => (!is_active, 1.),
+        LDtkDepthDisplayMode::Fade(alpha) if !is_active => (false, alpha),
+        LDtkDepthDisplayMode::Fade(_) => (false, 1.),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::ldtk::test_fixtures::sample_json;
+
+    #[test]
+    fn tiles_spawned_in_a_faded_level_are_faded() {
+        let mut world = World::new();
+        world.insert_resource(LDtkDepthDisplay {
+            mode: LDtkDepthDisplayMode::Fade(0.5),
+            active_depth: 1,
+        });
+        world.init_resource::<Assets<ColorMaterial>>();
+        let sample_world = sample_json().worlds[0].clone();
+        let level_iid = sample_world.levels[0].iid.clone();
+        let ldtk_world_entity = world
+            .spawn(LDtkWorld::new(
+                sample_world,
+                HashSet::from([level_iid.clone()]),
+            ))
+            .id();
+        let level_entity = world
+            .spawn((LDtkLevel(level_iid), ChildOf(ldtk_world_entity)))
+            .id();
+        let depth_display_system = world.register_system(apply_ldtk_depth_display);
+        world.run_system(depth_display_system).unwrap();
+
+        let tile_entity = world
+            .spawn((LDtkTile, Sprite::default(), ChildOf(level_entity)))
+            .id();
+        world.run_system(depth_display_system).unwrap();
+
+        let sprite = world.get::<Sprite>(tile_entity).unwrap();
+        assert_eq!(sprite.color.alpha(), 0.5);
+    }
+}
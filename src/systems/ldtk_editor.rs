@@ -0,0 +1,583 @@
+use std::collections::HashSet;
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    asset::prelude::LDtkProject,
+    components::prelude::*,
+    events::prelude::EntityInstanceSpawned,
+    ldtk::prelude::*,
+    messages::prelude::{LDtkEdit, LDtkIntGridChanged},
+    resources::prelude::{LDtkEditor, LDtkEditorTool},
+    systems::{
+        ldtk::{ldtk_entity_bundle, respawn_ldtk_tiles},
+        ldtk_tile_mesh::LDtkTileRenderer,
+    },
+};
+
+enum LevelChange {
+    GridTile {
+        layer_iid: String,
+        grid_coord: IVec2,
+        tile: Option<TileInstance>,
+    },
+    IntGridValue {
+        layer_iid: String,
+        grid_coord: IVec2,
+        value: i64,
+    },
+    EntityInstance {
+        layer_iid: String,
+        entity_iid: String,
+        entity_instance: Option<Box<EntityInstance>>,
+    },
+}
+
+impl LevelChange {
+    fn resolve(
+        ldtk_edit: &LDtkEdit,
+        level: &Level,
+        level_world_position: IVec2,
+        json_data: &LdtkJson,
+    ) -> Option<Self> {
+        match ldtk_edit {
+            LDtkEdit::PaintTile {
+                layer_iid,
+                grid_coord,
+                tile_id,
+                flip_bits,
+            } => {
+                let layer = tiles_layer_cell(level, layer_iid, *grid_coord)?;
+                let tileset_def = json_data
+                    .defs
+                    .tilesets
+                    .iter()
+                    .find(|tileset_def| Some(tileset_def.uid) == layer.tileset_def_uid)?;
+
+                Some(Self::GridTile {
+                    layer_iid: layer_iid.clone(),
+                    grid_coord: *grid_coord,
+                    tile: Some(layer.new_grid_tile(tileset_def, *grid_coord, *tile_id, *flip_bits)),
+                })
+            }
+            LDtkEdit::EraseTile {
+                layer_iid,
+                grid_coord,
+            } => {
+                tiles_layer_cell(level, layer_iid, *grid_coord)?;
+
+                Some(Self::GridTile {
+                    layer_iid: layer_iid.clone(),
+                    grid_coord: *grid_coord,
+                    tile: None,
+                })
+            }
+            LDtkEdit::SetIntGridValue {
+                layer_iid,
+                grid_coord,
+                value,
+            } => {
+                let layer = level.layer_instance(layer_iid)?;
+                if !matches!(layer.layer_instance_type, LayerInstanceType::IntGrid)
+                    || !layer.contains_grid_coord(*grid_coord)
+                {
+                    return None;
+                }
+
+                Some(Self::IntGridValue {
+                    layer_iid: layer_iid.clone(),
+                    grid_coord: *grid_coord,
+                    value: *value,
+                })
+            }
+            LDtkEdit::AddEntity {
+                layer_iid,
+                identifier,
+                px,
+            } => {
+                let layer = level.layer_instance(layer_iid)?;
+                if !matches!(layer.layer_instance_type, LayerInstanceType::Entities) {
+                    return None;
+                }
+                let entity_def = json_data
+                    .defs
+                    .entities
+                    .iter()
+                    .find(|entity_def| &entity_def.identifier == identifier)?;
+                let entity_instance =
+                    EntityInstance::new(entity_def, level_world_position, layer, *px);
+
+                Some(Self::EntityInstance {
+                    layer_iid: layer_iid.clone(),
+                    entity_iid: entity_instance.iid.clone(),
+                    entity_instance: Some(Box::new(entity_instance)),
+                })
+            }
+            LDtkEdit::MoveEntity { entity_iid, px } => {
+                let (layer, entity_instance) = level.entity_instance(entity_iid)?;
+                let mut entity_instance = entity_instance.clone();
+                entity_instance.set_px(level_world_position, layer, *px);
+
+                Some(Self::EntityInstance {
+                    layer_iid: layer.iid.clone(),
+                    entity_iid: entity_iid.clone(),
+                    entity_instance: Some(Box::new(entity_instance)),
+                })
+            }
+            LDtkEdit::DeleteEntity { entity_iid } => {
+                let (layer, _) = level.entity_instance(entity_iid)?;
+
+                Some(Self::EntityInstance {
+                    layer_iid: layer.iid.clone(),
+                    entity_iid: entity_iid.clone(),
+                    entity_instance: None,
+                })
+            }
+        }
+    }
+
+    fn layer_iid(&self) -> &str {
+        match self {
+            Self::GridTile { layer_iid, .. }
+            | Self::IntGridValue { layer_iid, .. }
+            | Self::EntityInstance { layer_iid, .. } => layer_iid,
+        }
+    }
+
+    fn apply(&self, level: &mut Level) {
+        let Some(layer) = level.layer_instance_mut(self.layer_iid()) else {
+            return;
+        };
+
+        match self {
+            Self::GridTile {
+                grid_coord, tile, ..
+            } => layer.set_grid_tile(*grid_coord, tile.clone()),
+            Self::IntGridValue {
+                grid_coord, value, ..
+            } => {
+                layer.set_int_grid_value(*grid_coord, *value);
+            }
+            Self::EntityInstance {
+                entity_iid,
+                entity_instance,
+                ..
+            } => {
+                let index = layer
+                    .entity_instances
+                    .iter()
+                    .position(|entity_instance| &entity_instance.iid == entity_iid);
+
+                match (index, entity_instance) {
+                    (Some(index), Some(entity_instance)) => {
+                        layer.entity_instances[index] = entity_instance.as_ref().clone()
+                    }
+                    (Some(index), None) => {
+                        layer.entity_instances.remove(index);
+                    }
+                    (None, Some(entity_instance)) => layer
+                        .entity_instances
+                        .push(entity_instance.as_ref().clone()),
+                    (None, None) => {}
+                }
+            }
+        }
+    }
+}
+
+fn tiles_layer_cell<'a>(
+    level: &'a Level,
+    layer_iid: &str,
+    grid_coord: IVec2,
+) -> Option<&'a LayerInstance> {
+    level.layer_instance(layer_iid).filter(|layer| {
+        matches!(layer.layer_instance_type, LayerInstanceType::Tiles)
+            && layer.contains_grid_coord(grid_coord)
+    })
+}
+
+fn edit_targets_level(ldtk_edit: &LDtkEdit, level: &Level) -> bool {
+    match ldtk_edit {
+        LDtkEdit::PaintTile { layer_iid, .. }
+        | LDtkEdit::EraseTile { layer_iid, .. }
+        | LDtkEdit::SetIntGridValue { layer_iid, .. }
+        | LDtkEdit::AddEntity { layer_iid, .. } => level.layer_instance(layer_iid).is_some(),
+        LDtkEdit::MoveEntity { entity_iid, .. } | LDtkEdit::DeleteEntity { entity_iid } => {
+            level.entity_instance(entity_iid).is_some()
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn apply_ldtk_edits(
+    mut commands: Commands,
+    mut ldtk_edits: MessageReader<LDtkEdit>,
+    mut ldtk_project_assets: ResMut<Assets<LDtkProject>>,
+    ldtk_project_handle_query: Query<&LDtkProjectHandle>,
+    mut ldtk_world_query: Query<(&mut LDtkWorld, &ChildOf)>,
+    ldtk_layer_query: Query<(Entity, &LDtkLayer, Option<&LDtkTileChunks>)>,
+    mut tile_renderer: LDtkTileRenderer,
+    mut ldtk_entity_query: Query<(Entity, &LDtkEntity, &mut Transform)>,
+    mut int_grid_changes: MessageWriter<LDtkIntGridChanged>,
+) {
+    ldtk_edits.read().for_each(|ldtk_edit| {
+        let Some((mut ldtk_world, child_of)) =
+            ldtk_world_query.iter_mut().find(|(ldtk_world, _)| {
+                ldtk_world
+                    .levels()
+                    .any(|level| edit_targets_level(ldtk_edit, level))
+            })
+        else {
+            return;
+        };
+        let ldtk_world = &mut *ldtk_world;
+        let Some((level_iid, level_world_position)) = ldtk_world
+            .world
+            .level_world_positions()
+            .find(|(level, _)| edit_targets_level(ldtk_edit, level))
+            .map(|(level, world_position)| (level.iid.clone(), world_position))
+        else {
+            return;
+        };
+        let Some(level) = ldtk_world
+            .world
+            .levels
+            .iter_mut()
+            .find(|level| level.iid == level_iid)
+        else {
+            return;
+        };
+
+        let Some(ldtk_project) = ldtk_project_handle_query
+            .get(child_of.parent())
+            .ok()
+            .and_then(|ldtk_project_handle| ldtk_project_assets.get_mut(ldtk_project_handle))
+        else {
+            return;
+        };
+
+        let Some(level_change) = LevelChange::resolve(
+            ldtk_edit,
+            level,
+            level_world_position,
+            ldtk_project.json_data(),
+        ) else {
+            return;
+        };
+
+        match ldtk_project.find_level_by_iid_mut(&level.iid) {
+            Some(project_level) => {
+                // Levels saved in their own file have no layers in the project, the loaded
+                // ones are copied in so `LDtkProject::save` writes the edit to the `.ldtkl`
+                if project_level.layer_instances.is_none() {
+                    project_level.layer_instances = level.layer_instances.clone();
+                }
+                level_change.apply(project_level);
+            }
+            None => warn!(
+                "Level {} is not in its project, the edit won't be saved",
+                level.iid
+            ),
+        }
+        level_change.apply(level);
+
+        // Auto-layer tiles of the spawned level are refreshed by `refresh_ldtk_auto_layers`
+        if let LevelChange::IntGridValue {
+            layer_iid,
+            grid_coord,
+            ..
+        } = &level_change
+        {
+            let int_grid_change = LDtkIntGridChanged::cell(layer_iid.clone(), *grid_coord);
+            ldtk_project.refresh_auto_layers(&level.iid, layer_iid, int_grid_change.region);
+            int_grid_changes.write(int_grid_change);
+            return;
+        }
+
+        let Some(layer) = level.layer_instance(level_change.layer_iid()) else {
+            return;
+        };
+        let Some((layer_entity, _, ldtk_tile_chunks)) = ldtk_layer_query
+            .iter()
+            .find(|(_, ldtk_layer, _)| ldtk_layer.0 == layer.iid)
+        else {
+            return;
+        };
+
+        match &level_change {
+            LevelChange::GridTile { grid_coord, .. } => {
+                respawn_ldtk_tiles(
+                    &mut commands,
+                    &mut tile_renderer,
+                    layer_entity,
+                    ldtk_tile_chunks,
+                    layer,
+                    &HashSet::from([*grid_coord]),
+                    &ldtk_world.tileset_handles,
+                    &ldtk_world.opaque_tiles,
+                );
+            }
+            LevelChange::IntGridValue { .. } => {}
+            LevelChange::EntityInstance {
+                entity_iid,
+                entity_instance,
+                ..
+            } => {
+                let spawned_entity = ldtk_entity_query
+                    .iter_mut()
+                    .find(|(_, ldtk_entity, _)| &ldtk_entity.0 == entity_iid);
+
+                match (spawned_entity, entity_instance) {
+                    (Some((_, _, mut transform)), Some(entity_instance)) => {
+                        let translation = layer.px_to_translation(entity_instance.px);
+                        transform.translation.x = translation.x;
+                        transform.translation.y = translation.y;
+                    }
+                    (Some((entity, _, _)), None) => {
+                        commands.entity(entity).despawn();
+                    }
+                    (None, Some(entity_instance)) => {
+                        let entity = commands
+                            .spawn((
+                                ldtk_entity_bundle(layer, entity_instance),
+                                ChildOf(layer_entity),
+                            ))
+                            .id();
+                        commands.trigger(EntityInstanceSpawned {
+                            entity,
+                            level_iid: level.iid.clone(),
+                            layer_iid: layer.iid.clone(),
+                            entity_iid: entity_iid.clone(),
+                        });
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn handle_ldtk_editor_input(
+    ldtk_editor: Res<LDtkEditor>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    ldtk_world_query: Query<&LDtkWorld>,
+    ldtk_layer_query: Query<(&LDtkLayer, &GlobalTransform)>,
+    mut dragged_entity_iid: Local<Option<String>>,
+    mut ldtk_edits: MessageWriter<LDtkEdit>,
+) {
+    if !mouse_buttons.pressed(MouseButton::Left) {
+        *dragged_entity_iid = None;
+    }
+
+    if !ldtk_editor.enabled {
+        return;
+    }
+
+    let Some(layer_iid) = &ldtk_editor.layer_iid else {
+        return;
+    };
+
+    let Some(cursor_position) = window_query
+        .single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+    else {
+        return;
+    };
+
+    let Some(cursor_world_position) = camera_query
+        .iter()
+        .filter(|(camera, _)| camera.is_active)
+        .find_map(|(camera, camera_transform)| {
+            camera
+                .viewport_to_world_2d(camera_transform, cursor_position)
+                .ok()
+        })
+    else {
+        return;
+    };
+
+    let Some(layer) = ldtk_world_query
+        .iter()
+        .find_map(|ldtk_world| ldtk_world.layer_instance(layer_iid))
+    else {
+        return;
+    };
+
+    let Some((_, layer_transform)) = ldtk_layer_query
+        .iter()
+        .find(|(ldtk_layer, _)| &ldtk_layer.0 == layer_iid)
+    else {
+        return;
+    };
+
+    let translation = layer_transform
+        .affine()
+        .inverse()
+        .transform_point3(cursor_world_position.extend(0.))
+        .truncate();
+    let grid_coord = layer.translation_to_grid_coord(translation);
+    let px = layer.translation_to_px(translation).round().as_ivec2();
+    let cell_px = grid_coord * layer.grid_size as i32;
+
+    let pressed = mouse_buttons.pressed(MouseButton::Left);
+    let just_pressed = mouse_buttons.just_pressed(MouseButton::Left);
+    let layer_iid = layer_iid.clone();
+
+    match &ldtk_editor.tool {
+        LDtkEditorTool::PaintTile { tile_id, flip_bits }
+            if pressed
+                && !layer.grid_tiles.iter().any(|tile| {
+                    tile.px == cell_px && tile.t == *tile_id && tile.f == *flip_bits
+                }) =>
+        {
+            ldtk_edits.write(LDtkEdit::PaintTile {
+                layer_iid,
+                grid_coord,
+                tile_id: *tile_id,
+                flip_bits: *flip_bits,
+            });
+        }
+        LDtkEditorTool::EraseTile
+            if pressed && layer.grid_tiles.iter().any(|tile| tile.px == cell_px) =>
+        {
+            ldtk_edits.write(LDtkEdit::EraseTile {
+                layer_iid,
+                grid_coord,
+            });
+        }
+        LDtkEditorTool::IntGrid(value)
+            if pressed
+                && layer
+                    .int_grid_value(grid_coord)
+                    .is_some_and(|current_value| current_value != *value) =>
+        {
+            ldtk_edits.write(LDtkEdit::SetIntGridValue {
+                layer_iid,
+                grid_coord,
+                value: *value,
+            });
+        }
+        LDtkEditorTool::AddEntity(identifier) if just_pressed => {
+            ldtk_edits.write(LDtkEdit::AddEntity {
+                layer_iid,
+                identifier: identifier.clone(),
+                px,
+            });
+        }
+        LDtkEditorTool::MoveEntity => {
+            if just_pressed {
+                *dragged_entity_iid =
+                    entity_instance_at(layer, px).map(|entity| entity.iid.clone());
+            }
+
+            let Some(entity_iid) = dragged_entity_iid.as_ref() else {
+                return;
+            };
+            if layer
+                .entity_instances
+                .iter()
+                .any(|entity| &entity.iid == entity_iid && entity.px != px)
+            {
+                ldtk_edits.write(LDtkEdit::MoveEntity {
+                    entity_iid: entity_iid.clone(),
+                    px,
+                });
+            }
+        }
+        LDtkEditorTool::DeleteEntity if just_pressed => {
+            if let Some(entity) = entity_instance_at(layer, px) {
+                ldtk_edits.write(LDtkEdit::DeleteEntity {
+                    entity_iid: entity.iid.clone(),
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
+fn entity_instance_at(layer: &LayerInstance, px: IVec2) -> Option<&EntityInstance> {
+    layer.entity_instances.iter().rev().find(|entity| {
+        let size = IVec2::new(entity.width as i32, entity.height as i32);
+        let min = entity.px - (entity.pivot * size.as_vec2()).as_ivec2();
+        IRect::from_corners(min, min + size).contains(px)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{asset::AssetPath, ecs::system::RunSystemOnce};
+
+    use super::*;
+    use crate::ldtk::test_fixtures::{
+        sample_json, sample_layer, sample_tileset_handles, tile_world,
+    };
+
+    #[test]
+    fn edits_of_external_levels_are_saved() {
+        let mut world = tile_world();
+        world.init_resource::<Messages<LDtkEdit>>();
+        world.init_resource::<Messages<LDtkIntGridChanged>>();
+
+        let loaded_world = sample_json().worlds[0].clone();
+        let level_iid = loaded_world.levels[0].iid.clone();
+        let mut json_data = sample_json();
+        json_data.external_levels = true;
+        let project_level = &mut json_data.worlds[0].levels[0];
+        project_level.external_rel_path = Some("external/Level_0.ldtkl".to_string());
+        project_level.layer_instances = None;
+
+        let ldtk_project_handle =
+            world
+                .resource_mut::<Assets<LDtkProject>>()
+                .add(LDtkProject::new(
+                    &AssetPath::from("external.ldtk"),
+                    json_data,
+                ));
+        let ldtk_project_entity = world
+            .spawn(LDtkProjectHandle(ldtk_project_handle.clone()))
+            .id();
+        world.spawn((
+            LDtkWorld {
+                tileset_handles: sample_tileset_handles(),
+                ..LDtkWorld::new(loaded_world, HashSet::from([level_iid.clone()]))
+            },
+            ChildOf(ldtk_project_entity),
+        ));
+
+        let layer = sample_layer();
+        let grid_coord = IVec2::new(3, 1);
+        world.write_message(LDtkEdit::SetIntGridValue {
+            layer_iid: layer.iid.clone(),
+            grid_coord,
+            value: 2,
+        });
+        world.run_system_once(apply_ldtk_edits).unwrap();
+
+        let project_dir = std::env::temp_dir().join(format!("bevy_ldtk_{level_iid}"));
+        let ldtk_project_assets = world.resource::<Assets<LDtkProject>>();
+        ldtk_project_assets
+            .get(&ldtk_project_handle)
+            .unwrap()
+            .save(project_dir.join("external.ldtk"))
+            .unwrap();
+        let saved_level: Level = serde_json::from_slice(
+            &std::fs::read(project_dir.join("external/Level_0.ldtkl")).unwrap(),
+        )
+        .unwrap();
+        let saved: LdtkJson =
+            serde_json::from_slice(&std::fs::read(project_dir.join("external.ldtk")).unwrap())
+                .unwrap();
+        std::fs::remove_dir_all(&project_dir).unwrap();
+
+        let saved_layer = &saved_level.layer_instances.as_ref().unwrap()[0];
+        assert_eq!(
+            saved_layer.int_grid_csv[layer.coord_id(grid_coord) as usize],
+            2
+        );
+        assert!(saved.worlds[0].levels[0].layer_instances.is_none());
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use bevy::{prelude::*, transform::helper::TransformHelper};
+
+use crate::components::prelude::LDtkParallax;
+
+type LDtkParallaxQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static LDtkParallax,
+        &'static ChildOf,
+        &'static mut Transform,
+    ),
+>;
+
+pub(crate) fn apply_ldtk_parallax(
+    camera_query: Query<(Entity, &Camera)>,
+    mut transform_params: ParamSet<(TransformHelper, LDtkParallaxQuery)>,
+) {
+    let Some(camera_entity) = camera_query
+        .iter()
+        .filter(|(_, camera)| camera.is_active)
+        .max_by_key(|(_, camera)| camera.order)
+        .map(|(camera_entity, _)| camera_entity)
+    else {
+        return;
+    };
+
+    let level_entities = transform_params
+        .p1()
+        .iter()
+        .map(|(_, child_of, _)| child_of.parent())
+        .collect::<Vec<_>>();
+    let transform_helper = transform_params.p0();
+    let Ok(camera_transform) = transform_helper.compute_global_transform(camera_entity) else {
+        return;
+    };
+    let camera_positions = level_entities
+        .into_iter()
+        .filter_map(|level_entity| {
+            let level_transform = transform_helper
+                .compute_global_transform(level_entity)
+                .ok()?;
+            let camera_position = level_transform
+                .affine()
+                .inverse()
+                .transform_point3(camera_transform.translation());
+            Some((level_entity, camera_position.truncate()))
+        })
+        .collect::<HashMap<_, _>>();
+
+    transform_params
+        .p1()
+        .iter_mut()
+        .for_each(|(parallax, child_of, mut transform)| {
+            let Some(camera_position) = camera_positions.get(&child_of.parent()) else {
+                return;
+            };
+
+            let offset = (camera_position - parallax.level_center) * parallax.factor;
+            let scale = if parallax.scaling {
+                Vec2::ONE - parallax.factor
+            } else {
+                Vec2::ONE
+            };
+            let translation = parallax.level_center
+                + (parallax.rest_translation - parallax.level_center) * scale
+                + offset;
+
+            transform.set_if_neq(Transform {
+                translation: translation.extend(transform.translation.z),
+                scale: scale.extend(transform.scale.z),
+                ..*transform
+            });
+        });
+}
@@ -0,0 +1,12 @@
+use bevy::ecs::schedule::SystemSet;
+
+/// Run in this order before transform propagation
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LDtkSystems {
+    /// Applies `LevelSelection`, level streaming and external level loads to `LDtkWorld`
+    SelectLevel,
+    /// Spawns and despawns levels, and respawns the tiles of refreshed auto-layers
+    Spawn,
+    /// Updates `CurrentLevel`, the depth display of the spawned levels and layer parallax
+    PostSpawn,
+}
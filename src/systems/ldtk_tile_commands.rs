@@ -0,0 +1,213 @@
+use std::collections::HashSet;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    asset::prelude::LDtkProject,
+    components::prelude::*,
+    ldtk::prelude::*,
+    systems::{ldtk::respawn_ldtk_tiles, ldtk_tile_mesh::LDtkTileRenderer},
+};
+
+/// The project asset is left untouched. Tiles of auto-layers are replaced when their rules
+/// are refreshed.
+#[derive(SystemParam)]
+pub struct LDtkTileCommands<'w, 's> {
+    commands: Commands<'w, 's>,
+    ldtk_project_assets: Res<'w, Assets<LDtkProject>>,
+    ldtk_project_handle_query: Query<'w, 's, &'static LDtkProjectHandle>,
+    ldtk_world_query: Query<'w, 's, (&'static mut LDtkWorld, &'static ChildOf)>,
+    ldtk_layer_query: Query<'w, 's, (Entity, &'static LDtkLayer, Option<&'static LDtkTileChunks>)>,
+    tile_renderer: LDtkTileRenderer<'w>,
+}
+
+impl LDtkTileCommands<'_, '_> {
+    /// Returns `false` when the layer or the cell doesn't exist
+    pub fn set_tile(
+        &mut self,
+        layer_iid: &str,
+        grid_coord: IVec2,
+        tile_id: i64,
+        flip_bits: i64,
+    ) -> bool {
+        self.update_cell(layer_iid, grid_coord, |layer, tileset_def| {
+            let Some(tileset_def) = tileset_def else {
+                return false;
+            };
+            let tile = layer.new_grid_tile(tileset_def, grid_coord, tile_id, flip_bits);
+            layer.set_tile(grid_coord, Some(tile));
+            true
+        })
+    }
+
+    pub fn clear_tile(&mut self, layer_iid: &str, grid_coord: IVec2) -> bool {
+        self.update_cell(layer_iid, grid_coord, |layer, _| {
+            layer.set_tile(grid_coord, None);
+            true
+        })
+    }
+
+    pub fn replace_tile(
+        &mut self,
+        layer_iid: &str,
+        grid_coord: IVec2,
+        stack_index: usize,
+        tile_id: i64,
+        flip_bits: i64,
+    ) -> bool {
+        self.update_cell(layer_iid, grid_coord, |layer, tileset_def| {
+            let (Some(tileset_def), Some(tile)) =
+                (tileset_def, layer.stacked_tile_mut(grid_coord, stack_index))
+            else {
+                return false;
+            };
+            tile.t = tile_id;
+            tile.src = tileset_def.tile_src(tile_id);
+            tile.f = flip_bits;
+            true
+        })
+    }
+
+    /// Several edits of a cell in one run leave the tiles of the last one
+    fn update_cell(
+        &mut self,
+        layer_iid: &str,
+        grid_coord: IVec2,
+        update: impl FnOnce(&mut LayerInstance, Option<&TilesetDefinition>) -> bool,
+    ) -> bool {
+        let Some((mut ldtk_world, child_of)) = self
+            .ldtk_world_query
+            .iter_mut()
+            .find(|(ldtk_world, _)| ldtk_world.level_with_layer(layer_iid).is_some())
+        else {
+            return false;
+        };
+        let ldtk_world = &mut *ldtk_world;
+
+        let tileset_defs = self
+            .ldtk_project_handle_query
+            .get(child_of.parent())
+            .ok()
+            .and_then(|ldtk_project_handle| self.ldtk_project_assets.get(ldtk_project_handle))
+            .map(|ldtk_project| &ldtk_project.json_data().defs.tilesets);
+
+        let Some(layer) = ldtk_world
+            .world
+            .levels
+            .iter_mut()
+            .find_map(|level| level.layer_instance_mut(layer_iid))
+        else {
+            return false;
+        };
+        if !layer.contains_grid_coord(grid_coord) {
+            return false;
+        }
+
+        let tileset_def = tileset_defs.and_then(|tileset_defs| {
+            tileset_defs
+                .iter()
+                .find(|tileset_def| Some(tileset_def.uid) == layer.tileset_def_uid)
+        });
+        if !update(layer, tileset_def) {
+            return false;
+        }
+
+        let Some((layer_entity, _, ldtk_tile_chunks)) = self
+            .ldtk_layer_query
+            .iter()
+            .find(|(_, ldtk_layer, _)| ldtk_layer.0 == layer_iid)
+        else {
+            return true;
+        };
+
+        respawn_ldtk_tiles(
+            &mut self.commands,
+            &mut self.tile_renderer,
+            layer_entity,
+            ldtk_tile_chunks,
+            layer,
+            &HashSet::from([grid_coord]),
+            &ldtk_world.tileset_handles,
+            &ldtk_world.opaque_tiles,
+        );
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::ldtk::test_fixtures::{
+        sample_json, sample_layer, sample_project, sample_tileset_handles, tile_world,
+    };
+
+    fn cell_tiles(
+        world: &mut bevy::ecs::world::World,
+        layer_entity: Entity,
+        grid_coord: IVec2,
+    ) -> Vec<usize> {
+        let ldtk_tile_storage = world.get::<LDtkTileStorage>(layer_entity).unwrap();
+        let tile_entities = (0..)
+            .map_while(|stack_index| ldtk_tile_storage.get(&LDtkGridCoord(grid_coord), stack_index))
+            .collect::<Vec<_>>();
+        tile_entities
+            .into_iter()
+            .map(|tile_entity| {
+                let sprite = world.get::<Sprite>(tile_entity).unwrap();
+                sprite.texture_atlas.as_ref().unwrap().index
+            })
+            .collect()
+    }
+
+    #[test]
+    fn last_edit_of_a_cell_in_a_frame_wins() {
+        let mut world = tile_world();
+        let ldtk_project_handle = world
+            .resource_mut::<Assets<LDtkProject>>()
+            .add(sample_project());
+        let ldtk_project_entity = world.spawn(LDtkProjectHandle(ldtk_project_handle)).id();
+        world.spawn((
+            LDtkWorld {
+                tileset_handles: sample_tileset_handles(),
+                ..LDtkWorld::new(
+                    sample_json().worlds[0].clone(),
+                    HashSet::from([sample_json().worlds[0].levels[0].iid.clone()]),
+                )
+            },
+            ChildOf(ldtk_project_entity),
+        ));
+        let layer_iid = sample_layer().iid;
+        let layer_entity = world
+            .spawn((LDtkLayer(layer_iid.clone()), LDtkTileStorage::default()))
+            .id();
+        let grid_coord = IVec2::new(3, 1);
+
+        let edit_layer_iid = layer_iid.clone();
+        world
+            .run_system_once(move |mut ldtk_tile_commands: LDtkTileCommands| {
+                assert!(ldtk_tile_commands.set_tile(&edit_layer_iid, grid_coord, 1, 0));
+                assert!(ldtk_tile_commands.set_tile(&edit_layer_iid, grid_coord, 2, 0));
+            })
+            .unwrap();
+        assert_eq!(cell_tiles(&mut world, layer_entity, grid_coord), vec![2]);
+
+        let edit_layer_iid = layer_iid.clone();
+        world
+            .run_system_once(move |mut ldtk_tile_commands: LDtkTileCommands| {
+                assert!(ldtk_tile_commands.replace_tile(&edit_layer_iid, grid_coord, 0, 3, 0));
+                assert!(ldtk_tile_commands.clear_tile(&edit_layer_iid, grid_coord));
+            })
+            .unwrap();
+        assert!(cell_tiles(&mut world, layer_entity, grid_coord).is_empty());
+        assert_eq!(
+            world
+                .query_filtered::<Entity, With<LDtkTile>>()
+                .iter(&world)
+                .count(),
+            0
+        );
+    }
+}
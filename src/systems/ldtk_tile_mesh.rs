@@ -0,0 +1,207 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    asset::RenderAssetUsages,
+    ecs::system::SystemParam,
+    mesh::{Indices, PrimitiveTopology},
+    prelude::*,
+};
+
+use crate::{
+    components::prelude::*,
+    ldtk::prelude::*,
+    resources::prelude::{LDtkRenderBackend, LDtkSettings, LDtkTileCulling},
+    systems::ldtk::tile_grid_coord,
+};
+
+#[derive(SystemParam)]
+pub(crate) struct LDtkTileRenderer<'w> {
+    pub(crate) backend: Res<'w, LDtkRenderBackend>,
+    pub(crate) culling: Res<'w, LDtkTileCulling>,
+    settings: Res<'w, LDtkSettings>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    color_materials: ResMut<'w, Assets<ColorMaterial>>,
+    texture_atlas_layouts: Res<'w, Assets<TextureAtlasLayout>>,
+}
+
+impl LDtkTileRenderer<'_> {
+    pub(crate) fn stack_z_spacing(&self) -> f32 {
+        self.settings.layer_z_spacing
+    }
+
+    pub(crate) fn spawn_tile_chunks(
+        &mut self,
+        commands: &mut Commands,
+        layer_entity: Entity,
+        layer: &LayerInstance,
+        occluded_tiles: &HashSet<(IVec2, usize)>,
+        tileset_image_handle: &Handle<Image>,
+        tileset_texture_atlas_layout_handle: &Handle<TextureAtlasLayout>,
+    ) {
+        let material = self
+            .color_materials
+            .add(ColorMaterial::from(tileset_image_handle.clone()));
+        let mut chunk_tiles = stacked_chunk_tiles(layer, occluded_tiles, |_| true);
+
+        let chunk_count =
+            (IVec2::new(layer.c_wid as i32, layer.c_hei as i32) + LDTK_TILE_CHUNK_SIZE - 1)
+                / LDTK_TILE_CHUNK_SIZE;
+        let chunks = (0..chunk_count.y)
+            .flat_map(|y| (0..chunk_count.x).map(move |x| IVec2::new(x, y)))
+            .map(|chunk_coord| {
+                let tiles = chunk_tiles.remove(&chunk_coord).unwrap_or_default();
+                let (mesh, ldtk_tile_chunk) =
+                    self.bake_chunk(layer, &tiles, tileset_texture_atlas_layout_handle);
+                let mesh_handle = self.meshes.add(mesh);
+                let chunk_entity = commands
+                    .spawn((
+                        Name::new(format!("Chunk {chunk_coord}")),
+                        ldtk_tile_chunk,
+                        Mesh2d(mesh_handle.clone()),
+                        MeshMaterial2d(material.clone()),
+                        ChildOf(layer_entity),
+                    ))
+                    .id();
+                (chunk_coord, (chunk_entity, mesh_handle))
+            })
+            .collect();
+
+        commands.entity(layer_entity).insert(LDtkTileChunks {
+            chunks,
+            texture_atlas_layout: tileset_texture_atlas_layout_handle.clone(),
+        });
+    }
+
+    pub(crate) fn rebake_tile_chunks(
+        &mut self,
+        commands: &mut Commands,
+        ldtk_tile_chunks: &LDtkTileChunks,
+        layer: &LayerInstance,
+        occluded_tiles: &HashSet<(IVec2, usize)>,
+        grid_coords: &HashSet<IVec2>,
+    ) {
+        let chunk_coords = grid_coords
+            .iter()
+            .map(|grid_coord| grid_coord.div_euclid(IVec2::splat(LDTK_TILE_CHUNK_SIZE)))
+            .collect::<HashSet<_>>();
+        let mut chunk_tiles = stacked_chunk_tiles(layer, occluded_tiles, |chunk_coord| {
+            chunk_coords.contains(&chunk_coord)
+        });
+
+        chunk_coords.iter().for_each(|chunk_coord| {
+            let Some((chunk_entity, mesh_handle)) = ldtk_tile_chunks.chunks.get(chunk_coord) else {
+                return;
+            };
+
+            let tiles = chunk_tiles.remove(chunk_coord).unwrap_or_default();
+            let (baked_mesh, ldtk_tile_chunk) =
+                self.bake_chunk(layer, &tiles, &ldtk_tile_chunks.texture_atlas_layout);
+            if let Some(mesh) = self.meshes.get_mut(mesh_handle) {
+                *mesh = baked_mesh;
+            }
+            commands.entity(*chunk_entity).insert(ldtk_tile_chunk);
+        });
+    }
+
+    fn bake_chunk(
+        &self,
+        layer: &LayerInstance,
+        tiles: &[(usize, &TileInstance)],
+        tileset_texture_atlas_layout_handle: &Handle<TextureAtlasLayout>,
+    ) -> (Mesh, LDtkTileChunk) {
+        let mut positions = Vec::new();
+        let mut uvs = Vec::new();
+        let mut colors = Vec::new();
+        let mut indices = Vec::new();
+        let mut chunk_tiles: HashMap<IVec2, Vec<TileInstance>> = HashMap::new();
+
+        let texture_atlas_layout = self
+            .texture_atlas_layouts
+            .get(tileset_texture_atlas_layout_handle);
+
+        tiles.iter().for_each(|&(stack_index, tile)| {
+            chunk_tiles
+                .entry(tile_grid_coord(layer, tile))
+                .or_default()
+                .push(tile.clone());
+
+            let Some((rect, atlas_size)) = texture_atlas_layout.and_then(|layout| {
+                let rect = layout.textures.get(tile.t as usize)?;
+                Some((rect.as_rect(), layout.size.as_vec2()))
+            }) else {
+                return;
+            };
+
+            let center = layer.px_to_translation(tile.px);
+            let half_size = rect.half_size();
+            let (min, max) = (center - half_size, center + half_size);
+            let z = stack_index as f32 * self.stack_z_spacing();
+
+            let (mut left, mut right) = (rect.min.x / atlas_size.x, rect.max.x / atlas_size.x);
+            let (mut top, mut bottom) = (rect.min.y / atlas_size.y, rect.max.y / atlas_size.y);
+            if tile.f & 0b01 != 0 {
+                std::mem::swap(&mut left, &mut right);
+            }
+            if tile.f & 0b10 != 0 {
+                std::mem::swap(&mut top, &mut bottom);
+            }
+
+            let first_index = positions.len() as u32;
+            positions.extend([
+                [min.x, min.y, z],
+                [max.x, min.y, z],
+                [max.x, max.y, z],
+                [min.x, max.y, z],
+            ]);
+            uvs.extend([[left, bottom], [right, bottom], [right, top], [left, top]]);
+            colors.extend([[1., 1., 1., (tile.a * layer.opacity) as f32]; 4]);
+            indices.extend([0, 1, 2, 0, 2, 3].map(|index| first_index + index));
+        });
+
+        let mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+        .with_inserted_indices(Indices::U32(indices));
+
+        (mesh, LDtkTileChunk { tiles: chunk_tiles })
+    }
+}
+
+/// Sorted by stack index, occluded tiles are left out
+fn stacked_chunk_tiles<'a>(
+    layer: &'a LayerInstance,
+    occluded_tiles: &HashSet<(IVec2, usize)>,
+    filter: impl Fn(IVec2) -> bool,
+) -> HashMap<IVec2, Vec<(usize, &'a TileInstance)>> {
+    let mut stack_sizes: HashMap<IVec2, usize> = HashMap::new();
+    let mut chunk_tiles: HashMap<IVec2, Vec<(usize, &TileInstance)>> = HashMap::new();
+
+    layer
+        .grid_tiles
+        .iter()
+        .chain(layer.auto_layer_tiles.iter())
+        .for_each(|tile| {
+            let stack_size = stack_sizes.entry(tile.px).or_default();
+            let stack_index = *stack_size;
+            *stack_size += 1;
+
+            let chunk_coord =
+                tile_grid_coord(layer, tile).div_euclid(IVec2::splat(LDTK_TILE_CHUNK_SIZE));
+            if filter(chunk_coord) && !occluded_tiles.contains(&(tile.px, stack_index)) {
+                chunk_tiles
+                    .entry(chunk_coord)
+                    .or_default()
+                    .push((stack_index, tile));
+            }
+        });
+
+    chunk_tiles
+        .values_mut()
+        .for_each(|tiles| tiles.sort_by_key(|(stack_index, _)| *stack_index));
+
+    chunk_tiles
+}
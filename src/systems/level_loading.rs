@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+
+use crate::{
+    asset::prelude::{LDtkExternalLevel, LDtkProject},
+    components::prelude::{LDtkLevelLoads, LDtkProjectHandle, LDtkWorld},
+    ldtk::prelude::LevelAccessor,
+    resources::prelude::LDtkLevelStreaming,
+    systems::level_selection::load_ldtk_tilesets,
+};
+
+/// Copies the layers of external levels into the `LDtkWorld` once loaded
+pub(crate) fn load_ldtk_levels(
+    asset_server: Res<AssetServer>,
+    level_streaming: Option<Res<LDtkLevelStreaming>>,
+    ldtk_project_assets: Res<Assets<LDtkProject>>,
+    ldtk_external_level_assets: Res<Assets<LDtkExternalLevel>>,
+    ldtk_project_handle_query: Query<&LDtkProjectHandle>,
+    mut ldtk_world_query: Query<(&mut LDtkWorld, &mut LDtkLevelLoads, &ChildOf)>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let max_pending_loads = level_streaming
+        .map(|level_streaming| level_streaming.max_pending_loads)
+        .unwrap_or(usize::MAX);
+
+    ldtk_world_query
+        .iter_mut()
+        .for_each(|(mut ldtk_world, mut ldtk_level_loads, child_of)| {
+            let Some(ldtk_project) = ldtk_project_handle_query
+                .get(child_of.parent())
+                .ok()
+                .and_then(|ldtk_project_handle| ldtk_project_assets.get(ldtk_project_handle))
+            else {
+                return;
+            };
+
+            let ldtk_level_loads = &mut *ldtk_level_loads;
+            let mut loaded_levels = Vec::new();
+            ldtk_level_loads.pending.retain(|level_iid, handle| {
+                if !ldtk_world.level_iids.contains(level_iid) {
+                    return false;
+                }
+                if let Some(ldtk_external_level) = ldtk_external_level_assets.get(&*handle) {
+                    loaded_levels.push((
+                        level_iid.clone(),
+                        ldtk_external_level.level().layer_instances.clone(),
+                    ));
+                    return false;
+                }
+                if asset_server.load_state(&*handle).is_failed() {
+                    warn!("Failed to load external level {level_iid}");
+                    ldtk_level_loads.failed.insert(level_iid.clone());
+                    return false;
+                }
+                true
+            });
+
+            if !loaded_levels.is_empty() {
+                loaded_levels
+                    .into_iter()
+                    .for_each(|(level_iid, layer_instances)| {
+                        if let Some(level) = ldtk_world.level_mut(&level_iid) {
+                            level.layer_instances = layer_instances;
+                        }
+                    });
+                load_ldtk_tilesets(
+                    &mut ldtk_world,
+                    ldtk_project,
+                    &asset_server,
+                    &mut texture_atlas_layouts,
+                );
+            }
+
+            ldtk_world.free_unselected_levels();
+            let ldtk_world = &mut *ldtk_world;
+            let level_iids = &ldtk_world.level_iids;
+
+            // Edited levels keep their layers in the project until it is saved
+            ldtk_world
+                .world
+                .levels
+                .iter_mut()
+                .filter(|level| level_iids.contains(&level.iid) && level.layer_instances.is_none())
+                .for_each(|level| {
+                    level.layer_instances = ldtk_project
+                        .find_world_level_by_iid(&level.iid)
+                        .and_then(|(_, project_level)| project_level.layer_instances.clone());
+                });
+
+            let free_loads = max_pending_loads.saturating_sub(ldtk_level_loads.pending.len());
+            let new_loads = ldtk_world
+                .levels()
+                .filter(|level| {
+                    level.layer_instances.is_none()
+                        && !ldtk_level_loads.pending.contains_key(&level.iid)
+                        && !ldtk_level_loads.failed.contains(&level.iid)
+                })
+                .filter_map(|level| {
+                    let rel_path = level.external_rel_path.as_ref()?;
+                    let level_path = ldtk_project.get_asset_rel_path(rel_path)?;
+                    Some((level.iid.clone(), asset_server.load(level_path)))
+                })
+                .take(free_loads)
+                .collect::<Vec<_>>();
+            ldtk_level_loads.pending.extend(new_loads);
+
+            let background_loads = ldtk_world
+                .levels()
+                .filter(|level| !ldtk_world.background_handles.contains_key(&level.iid))
+                .filter_map(|level| {
+                    let rel_path = level.bg_rel_path.as_ref()?;
+                    let background_path = ldtk_project.get_asset_rel_path(rel_path)?;
+                    Some((level.iid.clone(), asset_server.load(background_path)))
+                })
+                .collect::<Vec<_>>();
+            if !background_loads.is_empty() {
+                ldtk_world.background_handles.extend(background_loads);
+            }
+        });
+}
@@ -1,81 +1,150 @@
-use std::collections::HashMap;
-
 use bevy::prelude::*;
 
 use crate::{
     asset::prelude::LDtkProject,
     components::prelude::{LDtkProjectHandle, LDtkWorld},
     ldtk::prelude::LevelAccessor,
-    resources::prelude::LevelSelection,
+    resources::prelude::{LDtkSettings, LevelSelection},
 };
 
+/// Component selections take precedence, both are removed once applied
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn apply_level_selection(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     level_selection: Option<Res<LevelSelection>>,
     ldtk_project_assets: Res<Assets<LDtkProject>>,
-    ldtk_project_handle_query: Query<(Entity, &LDtkProjectHandle)>,
+    ldtk_project_handle_query: Query<(
+        Entity,
+        &LDtkProjectHandle,
+        Option<&LevelSelection>,
+        Option<&Children>,
+    )>,
+    ldtk_world_query: Query<&LDtkWorld>,
+    settings: Res<LDtkSettings>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
 ) {
-    let Some(level_selection) = level_selection else {
-        return;
-    };
-
-    if ldtk_project_handle_query
-        .iter()
-        .any(|(entity, ldtk_project_handle)| {
-            let Some(ldtk_project) = ldtk_project_assets.get(ldtk_project_handle) else {
-                return false;
-            };
-
-            let Some((world, level)) = ldtk_project.find_world_level(&level_selection) else {
-                return false;
-            };
+    let mut select_levels = |commands: &mut Commands,
+                             entity: Entity,
+                             ldtk_project_handle: &LDtkProjectHandle,
+                             children: Option<&Children>,
+                             level_selection: &LevelSelection| {
+        let Some(ldtk_project) = ldtk_project_assets.get(ldtk_project_handle) else {
+            return false;
+        };
 
-            commands.entity(entity).despawn_children();
+        let current_level_iid = children
+            .iter()
+            .flat_map(|children| ldtk_world_query.iter_many(children.iter()))
+            .find_map(|ldtk_world| ldtk_world.levels().next())
+            .map(|level| level.iid.as_str());
+        let Some((world, levels)) =
+            ldtk_project.find_world_levels(level_selection, current_level_iid)
+        else {
+            return false;
+        };
 
-            let tileset_uid_set = level.get_tileset_uid_set();
-            let tileset_defs = ldtk_project.get_tileset_defs(&tileset_uid_set);
+        commands.entity(entity).despawn_children();
 
-            let tileset_handles = tileset_defs
-                .iter()
-                .map(|&tileset_def| {
-                    let Some(rel_path) = &tileset_def.rel_path else {
-                        return None;
-                    };
+        let mut ldtk_world = LDtkWorld::new(
+            world.clone(),
+            levels.iter().map(|level| level.iid.clone()).collect(),
+        );
+        load_ldtk_tilesets(
+            &mut ldtk_world,
+            ldtk_project,
+            &asset_server,
+            &mut texture_atlas_layouts,
+        );
 
-                    let Some(tileset_path) = ldtk_project.get_asset_rel_path(rel_path) else {
-                        return None;
-                    };
+        let scale = 1. / settings.pixels_per_unit;
+        commands.entity(entity).insert(children![(
+            Name::new(world.identifier.clone()),
+            ldtk_world,
+            Transform::from_scale(Vec3::new(scale, scale, 1.)),
+        )]);
 
-                    let tileset_image_handle: Handle<Image> = asset_server.load(&tileset_path);
-                    let tileset_texture_atlas_layout_handle =
-                        texture_atlas_layouts.add(tileset_def);
+        true
+    };
 
-                    Some((
-                        tileset_def.uid,
-                        (
-                            tileset_def.tile_grid_size as usize,
-                            tileset_image_handle,
-                            tileset_texture_atlas_layout_handle,
-                        ),
-                    ))
-                })
-                .filter_map(|x| x)
-                .collect::<HashMap<_, _>>();
+    ldtk_project_handle_query.iter().for_each(
+        |(entity, ldtk_project_handle, entity_level_selection, children)| {
+            if let Some(entity_level_selection) = entity_level_selection
+                && select_levels(
+                    &mut commands,
+                    entity,
+                    ldtk_project_handle,
+                    children,
+                    entity_level_selection,
+                )
+            {
+                commands.entity(entity).remove::<LevelSelection>();
+            }
+        },
+    );
 
-            commands.entity(entity).insert(children![(
-                Name::new(world.identifier.clone()),
-                LDtkWorld {
-                    world: world.clone(),
-                    level: level.clone(),
-                    tileset_handles,
-                },
-            ),]);
+    let Some(level_selection) = level_selection else {
+        return;
+    };
 
-            true
+    if ldtk_project_handle_query
+        .iter()
+        .filter(|(_, _, entity_level_selection, _)| entity_level_selection.is_none())
+        .any(|(entity, ldtk_project_handle, _, children)| {
+            select_levels(
+                &mut commands,
+                entity,
+                ldtk_project_handle,
+                children,
+                &level_selection,
+            )
         })
     {
         commands.remove_resource::<LevelSelection>();
     }
 }
+
+/// External levels are skipped until their layers are loaded
+pub(crate) fn load_ldtk_tilesets(
+    ldtk_world: &mut LDtkWorld,
+    ldtk_project: &LDtkProject,
+    asset_server: &AssetServer,
+    texture_atlas_layouts: &mut Assets<TextureAtlasLayout>,
+) {
+    let tileset_uid_set = ldtk_world
+        .levels()
+        .flat_map(|level| level.get_tileset_uid_set())
+        .filter(|uid| !ldtk_world.tileset_handles.contains_key(uid))
+        .collect();
+
+    ldtk_project
+        .get_tileset_defs(&tileset_uid_set)
+        .into_iter()
+        .for_each(|tileset_def| {
+            if let Some(opaque_tiles) = tileset_def.opaque_tiles() {
+                ldtk_world
+                    .opaque_tiles
+                    .insert(tileset_def.uid, opaque_tiles);
+            }
+
+            let Some(tileset_path) = tileset_def
+                .rel_path
+                .as_ref()
+                .and_then(|rel_path| ldtk_project.get_asset_rel_path(rel_path))
+            else {
+                return;
+            };
+
+            let tileset_image_handle: Handle<Image> = asset_server.load(&tileset_path);
+            let tileset_texture_atlas_layout_handle = texture_atlas_layouts.add(tileset_def);
+
+            ldtk_world.tileset_handles.insert(
+                tileset_def.uid,
+                (
+                    tileset_def.tile_grid_size as usize,
+                    tileset_image_handle,
+                    tileset_texture_atlas_layout_handle,
+                ),
+            );
+        });
+}
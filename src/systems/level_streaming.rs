@@ -0,0 +1,75 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    components::prelude::{LDtkStreamingTarget, LDtkWorld},
+    ldtk::prelude::Level,
+    resources::prelude::LDtkLevelStreaming,
+};
+
+pub(crate) fn stream_ldtk_levels(
+    level_streaming: Option<Res<LDtkLevelStreaming>>,
+    streaming_target_query: Query<&GlobalTransform, With<LDtkStreamingTarget>>,
+    mut ldtk_world_query: Query<(&mut LDtkWorld, &GlobalTransform)>,
+) {
+    let Some(level_streaming) = level_streaming else {
+        return;
+    };
+
+    ldtk_world_query
+        .iter_mut()
+        .for_each(|(mut ldtk_world, world_transform)| {
+            let target_positions = streaming_target_query
+                .iter()
+                .map(|target_transform| ldtk_world_px(world_transform, target_transform))
+                .collect::<Vec<_>>();
+
+            let overlaps_target = |level_rect: Rect, half_size: Vec2| {
+                target_positions.iter().any(|&target_position| {
+                    !Rect::from_center_half_size(target_position, half_size)
+                        .intersect(level_rect)
+                        .is_empty()
+                })
+            };
+
+            let level_iids = ldtk_world
+                .world
+                .level_world_positions()
+                .filter(|&(level, world_position)| {
+                    let level_rect = level_world_rect(level, world_position);
+                    overlaps_target(level_rect, level_streaming.half_size)
+                        || (ldtk_world.level_iids.contains(&level.iid)
+                            && overlaps_target(
+                                level_rect,
+                                level_streaming.half_size + level_streaming.unload_margin,
+                            ))
+                })
+                .map(|(level, _)| level.iid.clone())
+                .collect::<HashSet<_>>();
+
+            if ldtk_world.level_iids != level_iids {
+                ldtk_world.level_iids = level_iids;
+            }
+        });
+}
+
+/// y pointing down
+pub(crate) fn ldtk_world_px(
+    world_transform: &GlobalTransform,
+    entity_transform: &GlobalTransform,
+) -> Vec2 {
+    let translation = world_transform
+        .affine()
+        .inverse()
+        .transform_point3(entity_transform.translation());
+    Vec2::new(translation.x, -translation.y)
+}
+
+pub(crate) fn level_world_rect(level: &Level, world_position: IVec2) -> Rect {
+    let px_size = IVec2::new(level.px_wid as i32, level.px_hei as i32);
+    Rect::from_corners(
+        world_position.as_vec2(),
+        (world_position + px_size).as_vec2(),
+    )
+}
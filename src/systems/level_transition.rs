@@ -0,0 +1,289 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use crate::{
+    components::prelude::{LDtkPlayer, LDtkWorld},
+    ldtk::prelude::{Level, NeighbourDirection, World},
+    messages::prelude::{ChangeLevelDepth, LevelTransition},
+    resources::prelude::{LDtkDepthDisplay, LevelSelection},
+    systems::level_streaming::{ldtk_world_px, level_world_rect},
+};
+
+/// Writes `level_iids` directly since going through `LevelSelection` would respawn the whole
+/// world
+pub(crate) fn transition_ldtk_levels(
+    level_selection: Option<Res<LevelSelection>>,
+    player_query: Query<&GlobalTransform, With<LDtkPlayer>>,
+    mut ldtk_world_query: Query<(Entity, &mut LDtkWorld, &GlobalTransform, &ChildOf)>,
+    level_selection_query: Query<(), With<LevelSelection>>,
+    mut depth_display: ResMut<LDtkDepthDisplay>,
+    mut player_levels: Local<HashMap<Entity, String>>,
+    mut level_transitions: MessageWriter<LevelTransition>,
+) {
+    if level_selection.is_some() {
+        return;
+    }
+    let Ok(player_transform) = player_query.single() else {
+        return;
+    };
+    player_levels.retain(|world_entity, _| ldtk_world_query.contains(*world_entity));
+
+    ldtk_world_query
+        .iter_mut()
+        .filter(|(_, _, _, child_of)| !level_selection_query.contains(child_of.parent()))
+        .for_each(|(world_entity, mut ldtk_world, world_transform, _)| {
+            let player_px = ldtk_world_px(world_transform, player_transform);
+            let level_rects = level_world_rects(&ldtk_world);
+            let contains_player = |level: &&Level| {
+                level_rects
+                    .get(level.iid.as_str())
+                    .is_some_and(|level_rect| level_rect.contains(player_px))
+            };
+
+            // The level at the active depth wins over the overlapping ones, then the level
+            // the player was already in
+            let previous_level = player_levels.get(&world_entity);
+            let player_levels_at_depth = |at_active_depth: bool| {
+                let active_depth = depth_display.active_depth;
+                ldtk_world
+                    .levels()
+                    .filter(contains_player)
+                    .filter(move |level| !at_active_depth || level.world_depth == active_depth)
+                    .min_by_key(|level| Some(&level.iid) != previous_level)
+            };
+            let Some(to) = player_levels_at_depth(true).or_else(|| player_levels_at_depth(false))
+            else {
+                return;
+            };
+
+            if previous_level != Some(&to.iid) {
+                if let Some(from_iid) = previous_level {
+                    let direction = ldtk_world.level(from_iid).and_then(|from| {
+                        from.neighbours
+                            .iter()
+                            .find(|neighbour| neighbour.level_iid == to.iid)?
+                            .direction()
+                    });
+                    depth_display.active_depth = to.world_depth;
+                    level_transitions.write(LevelTransition {
+                        from: from_iid.clone(),
+                        to: to.iid.clone(),
+                        direction,
+                    });
+                }
+                player_levels.insert(world_entity, to.iid.clone());
+            }
+
+            let level_iids = neighbourhood_level_iids(&ldtk_world.world, to);
+            if ldtk_world.level_iids != level_iids {
+                ldtk_world.level_iids = level_iids;
+            }
+        });
+}
+
+pub(crate) fn change_ldtk_level_depth(
+    mut change_level_depths: MessageReader<ChangeLevelDepth>,
+    player_query: Query<&GlobalTransform, With<LDtkPlayer>>,
+    mut ldtk_world_query: Query<(&mut LDtkWorld, &GlobalTransform)>,
+    mut depth_display: ResMut<LDtkDepthDisplay>,
+    mut level_transitions: MessageWriter<LevelTransition>,
+) {
+    let Some(&change_level_depth) = change_level_depths.read().last() else {
+        return;
+    };
+    let Ok(player_transform) = player_query.single() else {
+        return;
+    };
+    let direction = match change_level_depth {
+        ChangeLevelDepth::Up => NeighbourDirection::Above,
+        ChangeLevelDepth::Down => NeighbourDirection::Below,
+    };
+
+    let Some((mut ldtk_world, level_transition, level_iids, world_depth)) = ldtk_world_query
+        .iter_mut()
+        .find_map(|(ldtk_world, world_transform)| {
+            let player_px = ldtk_world_px(world_transform, player_transform);
+            let level_rects = level_world_rects(&ldtk_world);
+            let contains_player = |level_iid: &str| {
+                level_rects
+                    .get(level_iid)
+                    .is_some_and(|level_rect| level_rect.contains(player_px))
+            };
+
+            let from = ldtk_world
+                .levels()
+                .filter(|level| contains_player(&level.iid))
+                .min_by_key(|level| level.world_depth != depth_display.active_depth)?;
+            let to = ldtk_world
+                .world
+                .neighbour_levels(from, direction)
+                .filter(|to| contains_player(&to.iid))
+                .min_by_key(|to| (to.world_depth - from.world_depth).abs())?;
+
+            let level_transition = LevelTransition {
+                from: from.iid.clone(),
+                to: to.iid.clone(),
+                direction: Some(direction),
+            };
+            let level_iids = neighbourhood_level_iids(&ldtk_world.world, to);
+            let world_depth = to.world_depth;
+            Some((ldtk_world, level_transition, level_iids, world_depth))
+        })
+    else {
+        return;
+    };
+
+    if ldtk_world.level_iids != level_iids {
+        ldtk_world.level_iids = level_iids;
+    }
+    depth_display.active_depth = world_depth;
+    level_transitions.write(level_transition);
+}
+
+fn neighbourhood_level_iids(world: &World, level: &Level) -> HashSet<String> {
+    level
+        .neighbours
+        .iter()
+        .map(|neighbour| &neighbour.level_iid)
+        .filter(|level_iid| world.levels.iter().any(|level| level.iid == **level_iid))
+        .chain([&level.iid])
+        .cloned()
+        .collect()
+}
+
+fn level_world_rects(ldtk_world: &LDtkWorld) -> HashMap<&str, Rect> {
+    ldtk_world
+        .world
+        .level_world_positions()
+        .map(|(level, world_position)| {
+            (level.iid.as_str(), level_world_rect(level, world_position))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::message::Messages;
+
+    use super::*;
+    use crate::{components::prelude::LDtkProjectHandle, ldtk::test_fixtures::sample_json};
+
+    #[test]
+    fn neighbours_stay_selected_across_transitions() {
+        let mut world = bevy::ecs::world::World::new();
+        world.init_resource::<LDtkDepthDisplay>();
+        world.init_resource::<Messages<LevelTransition>>();
+        let sample_world = sample_json().worlds[0].clone();
+        let level_iids = sample_world
+            .levels
+            .iter()
+            .map(|level| level.iid.clone())
+            .collect::<Vec<_>>();
+
+        let ldtk_project_entity = world.spawn(LDtkProjectHandle(Handle::default())).id();
+        let ldtk_world_entity = world
+            .spawn((
+                LDtkWorld::new(sample_world, HashSet::from([level_iids[0].clone()])),
+                ChildOf(ldtk_project_entity),
+            ))
+            .id();
+        let player_entity = world
+            .spawn((
+                LDtkPlayer,
+                GlobalTransform::from_translation(Vec3::new(10., -10., 0.)),
+            ))
+            .id();
+        let transition_system = world.register_system(transition_ldtk_levels);
+
+        world.run_system(transition_system).unwrap();
+        let all_level_iids = level_iids.iter().cloned().collect::<HashSet<_>>();
+        assert_eq!(
+            world
+                .get::<LDtkWorld>(ldtk_world_entity)
+                .unwrap()
+                .level_iids,
+            all_level_iids
+        );
+        assert!(
+            world
+                .resource_mut::<Messages<LevelTransition>>()
+                .drain()
+                .next()
+                .is_none()
+        );
+
+        *world.get_mut::<GlobalTransform>(player_entity).unwrap() =
+            GlobalTransform::from_translation(Vec3::new(60., -10., 0.));
+        world.run_system(transition_system).unwrap();
+        assert_eq!(
+            world
+                .get::<LDtkWorld>(ldtk_world_entity)
+                .unwrap()
+                .level_iids,
+            all_level_iids
+        );
+        let level_transitions = world
+            .resource_mut::<Messages<LevelTransition>>()
+            .drain()
+            .collect::<Vec<_>>();
+        assert_eq!(level_transitions.len(), 1);
+        assert_eq!(level_transitions[0].from, level_iids[0]);
+        assert_eq!(level_transitions[0].to, level_iids[1]);
+        assert_eq!(
+            level_transitions[0].direction,
+            Some(NeighbourDirection::East)
+        );
+    }
+
+    #[test]
+    fn transitions_are_sent_between_levels_without_neighbour_entries() {
+        let mut world = bevy::ecs::world::World::new();
+        world.init_resource::<LDtkDepthDisplay>();
+        world.init_resource::<Messages<LevelTransition>>();
+        let mut sample_world = sample_json().worlds[0].clone();
+        sample_world
+            .levels
+            .iter_mut()
+            .for_each(|level| level.neighbours.clear());
+        let level_iids = sample_world
+            .levels
+            .iter()
+            .map(|level| level.iid.clone())
+            .collect::<Vec<_>>();
+
+        let ldtk_project_entity = world.spawn(LDtkProjectHandle(Handle::default())).id();
+        world.spawn((
+            LDtkWorld::new(sample_world, HashSet::from([level_iids[0].clone()])),
+            ChildOf(ldtk_project_entity),
+        ));
+        let player_entity = world
+            .spawn((
+                LDtkPlayer,
+                GlobalTransform::from_translation(Vec3::new(10., -10., 0.)),
+            ))
+            .id();
+        let transition_system = world.register_system(transition_ldtk_levels);
+        world.run_system(transition_system).unwrap();
+
+        // Only the player's level is kept selected, so the next one is selected again by hand
+        world
+            .query::<&mut LDtkWorld>()
+            .single_mut(&mut world)
+            .unwrap()
+            .level_iids
+            .insert(level_iids[1].clone());
+        *world.get_mut::<GlobalTransform>(player_entity).unwrap() =
+            GlobalTransform::from_translation(Vec3::new(60., -10., 0.));
+        world.run_system(transition_system).unwrap();
+
+        let level_transitions = world
+            .resource_mut::<Messages<LevelTransition>>()
+            .drain()
+            .collect::<Vec<_>>();
+        assert_eq!(level_transitions.len(), 1);
+        assert_eq!(level_transitions[0].from, level_iids[0]);
+        assert_eq!(level_transitions[0].to, level_iids[1]);
+        assert_eq!(level_transitions[0].direction, None);
+    }
+}
@@ -1,12 +1,43 @@
+mod current_level;
 mod ldtk;
+mod ldtk_auto_layer;
+mod ldtk_depth_display;
+mod ldtk_editor;
+mod ldtk_parallax;
+mod ldtk_systems;
+mod ldtk_tile_commands;
+mod ldtk_tile_mesh;
+mod level_loading;
 mod level_selection;
+mod level_streaming;
+mod level_transition;
 
 pub(crate) mod prelude {
+    use super::current_level;
     use super::ldtk;
+    use super::ldtk_auto_layer;
+    use super::ldtk_depth_display;
+    use super::ldtk_editor;
+    use super::ldtk_parallax;
+    use super::ldtk_systems;
+    use super::ldtk_tile_commands;
+    use super::level_loading;
     use super::level_selection;
+    use super::level_streaming;
+    use super::level_transition;
 
+    pub(crate) use current_level::update_current_level;
     pub(crate) use ldtk::spawn_ldtk_world;
+    pub(crate) use ldtk_auto_layer::refresh_ldtk_auto_layers;
+    pub(crate) use ldtk_depth_display::apply_ldtk_depth_display;
+    pub(crate) use ldtk_editor::{apply_ldtk_edits, handle_ldtk_editor_input};
+    pub(crate) use ldtk_parallax::apply_ldtk_parallax;
+    pub(crate) use level_loading::load_ldtk_levels;
     pub(crate) use level_selection::apply_level_selection;
+    pub(crate) use level_streaming::stream_ldtk_levels;
+    pub(crate) use level_transition::{change_ldtk_level_depth, transition_ldtk_levels};
 
     pub use ldtk::despawn_all_ldtk_worlds;
+    pub use ldtk_systems::LDtkSystems;
+    pub use ldtk_tile_commands::LDtkTileCommands;
 }